@@ -0,0 +1,601 @@
+//! Bonding curve shapes
+
+use {
+    crate::{
+        error::TokenError,
+        instructions::schedule::RoundSchedule,
+        instructions::trade::{
+            _currenRound, _priceForRound, mul_div_u64, new_calcBurnExactTokensForStable,
+            new_calcMintTokensForExactStableWithFee, FEE_PERCENT_POINT, MAX_TOTAL_SUPPLY,
+            PERCENT_DENOMINATOR, ROUND_VOLUME,
+        },
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::program_error::ProgramError,
+    uint::construct_uint,
+};
+construct_uint! {
+    pub struct U256(4);
+}
+
+/// Prices are quoted in stable units per whole distribution token.
+pub const TOKEN_UNIT: u64 = 10_000_000;
+const EXP_SCALE: u128 = 1_000_000_000_000_000_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    /// Price grows by one unit every `ROUND_VOLUME` tokens. Parameters unused.
+    SteppedLinear,
+    /// Price starts at `param_a` and grows by `param_b` percent points
+    /// (`100 * PERCENT_DENOMINATOR` is 100%) every `ROUND_VOLUME` tokens.
+    Exponential,
+    /// Price is `param_a + param_b * supply / TOKEN_UNIT`, without steps.
+    ContinuousLinear,
+    /// Constant product over virtual reserves: `param_a` virtual stable,
+    /// `param_b` virtual tokens.
+    ConstantProduct,
+    /// Rounds and prices read from the launch's `RoundSchedule` account.
+    Schedule,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveConfig {
+    pub kind: CurveKind,
+    pub param_a: u64,
+    pub param_b: u64,
+}
+
+impl CurveConfig {
+    pub const LEN: usize = 1 + 8 + 8;
+
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        let valid = match self.kind {
+            CurveKind::SteppedLinear | CurveKind::Schedule => true,
+            CurveKind::Exponential => self.param_a > 0,
+            CurveKind::ContinuousLinear => self.param_a > 0,
+            CurveKind::ConstantProduct => self.param_a > 0 && self.param_b > 0,
+        };
+        if !valid {
+            return Err(TokenError::InvalidCurve.into());
+        }
+        Ok(())
+    }
+
+    /// Tokens minted for `in_amount` stable (fee included) and the fee.
+    /// `schedule` is only read by `CurveKind::Schedule`. `fee_rate` is in the
+    /// units of `FEE_PERCENT_POINT`.
+    pub fn buy(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+        in_amount: u64,
+        fee_rate: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        if self.kind == CurveKind::SteppedLinear {
+            return Ok(new_calcMintTokensForExactStableWithFee(
+                supply, in_amount, fee_rate,
+            ));
+        }
+        let (clean, fee) = take_buy_fee(in_amount, fee_rate);
+        let out = match self.kind {
+            CurveKind::Exponential => segmented_buy(self, supply, clean),
+            CurveKind::ContinuousLinear => linear_buy(self.param_a, self.param_b, supply, clean),
+            CurveKind::ConstantProduct => cp_buy(self.param_a, self.param_b, supply, clean),
+            CurveKind::Schedule => Some(required(schedule)?.buy(supply, clean)?),
+            CurveKind::SteppedLinear => unreachable!(),
+        }
+        .ok_or(TokenError::MathOverflow)?;
+        Ok((out, fee))
+    }
+
+    /// Stable paid out for burning `in_amount` tokens (fee excluded) and the fee.
+    pub fn sell(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+        in_amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        if in_amount > supply {
+            return Err(TokenError::CurveSupplyExceeded.into());
+        }
+        if self.kind == CurveKind::SteppedLinear {
+            return Ok(new_calcBurnExactTokensForStable(supply, in_amount));
+        }
+        let gross = match self.kind {
+            CurveKind::Exponential => segmented_sell(self, supply, in_amount),
+            CurveKind::ContinuousLinear => {
+                linear_sell(self.param_a, self.param_b, supply, in_amount)
+            }
+            CurveKind::ConstantProduct => cp_sell(self.param_a, self.param_b, supply, in_amount),
+            CurveKind::Schedule => Some(required(schedule)?.sell(supply, in_amount)?),
+            CurveKind::SteppedLinear => unreachable!(),
+        }
+        .ok_or(TokenError::MathOverflow)?;
+        Ok(take_sell_fee(gross))
+    }
+
+    /// Largest curve supply the curve can sell.
+    pub fn max_supply(&self, schedule: Option<&RoundSchedule>) -> Result<u64, ProgramError> {
+        Ok(match self.kind {
+            CurveKind::ConstantProduct => MAX_TOTAL_SUPPLY.min(self.param_b - 1),
+            CurveKind::Schedule => required(schedule)?.max_supply(),
+            _ => MAX_TOTAL_SUPPLY,
+        })
+    }
+
+    /// Stable (fee included) needed to mint exactly `tokens` and the fee part.
+    pub fn stable_for_exact_tokens(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+        tokens: u64,
+        fee_rate: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        let cost = match self.kind {
+            CurveKind::SteppedLinear | CurveKind::Exponential => {
+                segmented_cost(self, supply, tokens)
+            }
+            CurveKind::ContinuousLinear => linear_cost(self.param_a, self.param_b, supply, tokens),
+            CurveKind::ConstantProduct => cp_cost(self.param_a, self.param_b, supply, tokens),
+            CurveKind::Schedule => segmented_cost(required(schedule)?, supply, tokens),
+        }
+        .ok_or(TokenError::MathOverflow)?;
+        add_buy_fee(cost, fee_rate).ok_or_else(|| TokenError::MathOverflow.into())
+    }
+
+    /// Marginal price of the next token at `supply`.
+    pub fn spot_price(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+    ) -> Result<u64, ProgramError> {
+        let price = match self.kind {
+            CurveKind::SteppedLinear => Some(_priceForRound(_currenRound(supply))),
+            CurveKind::Exponential => self.segment(supply).map(|(_, _, price)| price),
+            CurveKind::ContinuousLinear => mul_div_u64(self.param_b, supply, TOKEN_UNIT)
+                .and_then(|step| step.checked_add(self.param_a)),
+            CurveKind::ConstantProduct => {
+                cp_reserves(self.param_a, self.param_b, supply).and_then(|(stable, tokens)| {
+                    let price = stable.checked_mul(TOKEN_UNIT.into())? / U256::from(tokens);
+                    to_u64(price)
+                })
+            }
+            CurveKind::Schedule => Some(required(schedule)?.spot_price(supply)?),
+        };
+        price.ok_or_else(|| TokenError::MathOverflow.into())
+    }
+
+    /// Round of the next token at `supply`, counted from 1, or 0 for curves
+    /// without rounds.
+    pub fn round(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+    ) -> Result<u64, ProgramError> {
+        Ok(match self.kind {
+            CurveKind::SteppedLinear | CurveKind::Exponential => _currenRound(supply),
+            CurveKind::Schedule => required(schedule)?.round(supply),
+            CurveKind::ContinuousLinear | CurveKind::ConstantProduct => 0,
+        })
+    }
+}
+
+fn required(schedule: Option<&RoundSchedule>) -> Result<&RoundSchedule, ProgramError> {
+    schedule.ok_or_else(|| TokenError::WrongScheduleAccount.into())
+}
+
+pub(crate) fn take_buy_fee(in_amount: u64, fee_rate: u64) -> (u64, u64) {
+    let clean = (in_amount as u128 * 100 * PERCENT_DENOMINATOR as u128
+        / (100 * PERCENT_DENOMINATOR as u128 + fee_rate as u128)) as u64;
+    (clean, in_amount - clean)
+}
+
+/// Inverse of `take_buy_fee`, rounded so the clean part covers `cost`.
+pub(crate) fn add_buy_fee(cost: u64, fee_rate: u64) -> Option<(u64, u64)> {
+    let denominator = 100 * PERCENT_DENOMINATOR as u128;
+    let in_amount = (cost as u128 * (denominator + fee_rate as u128))
+        .checked_add(denominator - 1)?
+        / denominator;
+    let in_amount = u64::try_from(in_amount).ok()?;
+    Some((in_amount, in_amount - cost))
+}
+
+pub(crate) fn take_sell_fee(gross: u64) -> (u64, u64) {
+    let net = (gross as u128 * (100 * PERCENT_DENOMINATOR - FEE_PERCENT_POINT) as u128
+        / (100 * PERCENT_DENOMINATOR) as u128) as u64;
+    (net, gross - net)
+}
+
+fn to_u64(value: U256) -> Option<u64> {
+    if value > U256::from(u64::MAX) {
+        None
+    } else {
+        Some(value.as_u64())
+    }
+}
+
+/// Curves whose price is constant within each segment of supply.
+pub(crate) trait Segments {
+    /// `(start, end, price)` of the segment holding the token at `supply`.
+    fn segment(&self, supply: u64) -> Option<(u64, u64, u64)>;
+}
+
+impl Segments for CurveConfig {
+    fn segment(&self, supply: u64) -> Option<(u64, u64, u64)> {
+        let round = supply / ROUND_VOLUME;
+        let start = round * ROUND_VOLUME;
+        let price = match self.kind {
+            CurveKind::SteppedLinear => _priceForRound(round + 1),
+            CurveKind::Exponential => exp_price(self.param_a, self.param_b, round)?,
+            _ => return None,
+        };
+        Some((start, start.saturating_add(ROUND_VOLUME), price))
+    }
+}
+
+/// `price_a * (1 + growth)^round`, in fixed point.
+fn exp_price(price_a: u64, growth: u64, round: u64) -> Option<u64> {
+    let scale = U256::from(EXP_SCALE);
+    let denominator = 100 * PERCENT_DENOMINATOR as u128;
+    let mut base = U256::from(denominator + growth as u128) * scale / U256::from(denominator);
+    let mut result = scale;
+    let mut n = round;
+    loop {
+        if n & 1 == 1 {
+            result = result.checked_mul(base)? / scale;
+        }
+        n >>= 1;
+        if n == 0 {
+            break;
+        }
+        base = base.checked_mul(base)? / scale;
+    }
+    to_u64(result.checked_mul(price_a.into())? / scale)
+}
+
+/// Stops at the last segment, so the result may be worth less than `amount`.
+pub(crate) fn segmented_buy(segments: &impl Segments, supply: u64, amount: u64) -> Option<u64> {
+    let mut supply = supply;
+    let mut rest = amount;
+    let mut out: u64 = 0;
+    while rest > 0 {
+        let Some((_, end, price)) = segments.segment(supply) else {
+            break;
+        };
+        let volume = end - supply;
+        let cost = mul_div_u64(volume, price, TOKEN_UNIT)?;
+        if rest > cost {
+            out = out.checked_add(volume)?;
+            rest -= cost;
+            supply = end;
+        } else {
+            out = out.checked_add(mul_div_u64(rest, TOKEN_UNIT, price)?)?;
+            rest = 0;
+        }
+    }
+    Some(out)
+}
+
+pub(crate) fn segmented_sell(segments: &impl Segments, supply: u64, amount: u64) -> Option<u64> {
+    let mut supply = supply;
+    let mut rest = amount;
+    let mut out: u64 = 0;
+    while rest > 0 {
+        let (start, _, price) = segments.segment(supply - 1)?;
+        let volume = supply - start;
+        if rest > volume {
+            out = out.checked_add(mul_div_u64(volume, price, TOKEN_UNIT)?)?;
+            rest -= volume;
+            supply = start;
+        } else {
+            out = out.checked_add(mul_div_u64(rest, price, TOKEN_UNIT)?)?;
+            rest = 0;
+        }
+    }
+    Some(out)
+}
+
+/// Cost of the next `tokens`, each segment rounded up.
+fn segmented_cost(segments: &impl Segments, supply: u64, tokens: u64) -> Option<u64> {
+    let mut supply = supply;
+    let mut rest = tokens;
+    let mut cost: u64 = 0;
+    while rest > 0 {
+        let (_, end, price) = segments.segment(supply)?;
+        let volume = rest.min(end - supply);
+        let volume_cost = ceil_div(
+            U256::from(volume) * U256::from(price),
+            U256::from(TOKEN_UNIT),
+        );
+        cost = cost.checked_add(to_u64(volume_cost)?)?;
+        rest -= volume;
+        supply += volume;
+    }
+    Some(cost)
+}
+
+/// Twice the cost of the first `supply` tokens, scaled by `TOKEN_UNIT^2`.
+fn linear_area(price_a: u64, slope: u64, supply: u64) -> Option<U256> {
+    let supply = U256::from(supply);
+    let flat = U256::from(2u64) * U256::from(price_a) * U256::from(TOKEN_UNIT) * supply;
+    flat.checked_add(U256::from(slope).checked_mul(supply.checked_mul(supply)?)?)
+}
+
+fn linear_buy(price_a: u64, slope: u64, supply: u64, amount: u64) -> Option<u64> {
+    let unit = U256::from(TOKEN_UNIT);
+    if slope == 0 {
+        return to_u64(U256::from(amount) * unit / U256::from(price_a));
+    }
+    // slope * s1^2 + 2 * price_a * TOKEN_UNIT * s1 = target
+    let target = linear_area(price_a, slope, supply)?
+        .checked_add(U256::from(2u64) * unit * unit * U256::from(amount))?;
+    let half_b = U256::from(price_a) * unit;
+    let discriminant = half_b
+        .checked_mul(half_b)?
+        .checked_add(U256::from(slope).checked_mul(target)?)?;
+    let new_supply = (discriminant.integer_sqrt() - half_b) / U256::from(slope);
+    to_u64(new_supply).map(|new_supply| new_supply.saturating_sub(supply))
+}
+
+fn linear_cost(price_a: u64, slope: u64, supply: u64, tokens: u64) -> Option<u64> {
+    let unit = U256::from(TOKEN_UNIT);
+    let area = linear_area(price_a, slope, supply.checked_add(tokens)?)?
+        - linear_area(price_a, slope, supply)?;
+    to_u64(ceil_div(area, U256::from(2u64) * unit * unit))
+}
+
+fn linear_sell(price_a: u64, slope: u64, supply: u64, amount: u64) -> Option<u64> {
+    let unit = U256::from(TOKEN_UNIT);
+    let area = linear_area(price_a, slope, supply)? - linear_area(price_a, slope, supply - amount)?;
+    to_u64(area / (U256::from(2u64) * unit * unit))
+}
+
+/// Reserves at `supply`; the stable side is rounded up so the curve never
+/// pays out more than it took in.
+fn cp_reserves(virtual_stable: u64, virtual_tokens: u64, supply: u64) -> Option<(U256, u64)> {
+    let tokens = virtual_tokens
+        .checked_sub(supply)
+        .filter(|tokens| *tokens > 0)?;
+    let k = U256::from(virtual_stable) * U256::from(virtual_tokens);
+    Some((ceil_div(k, U256::from(tokens)), tokens))
+}
+
+fn ceil_div(a: U256, b: U256) -> U256 {
+    (a + b - U256::from(1u64)) / b
+}
+
+fn cp_buy(virtual_stable: u64, virtual_tokens: u64, supply: u64, amount: u64) -> Option<u64> {
+    let (stable, tokens) = cp_reserves(virtual_stable, virtual_tokens, supply)?;
+    let k = U256::from(virtual_stable) * U256::from(virtual_tokens);
+    let new_tokens = ceil_div(k, stable + U256::from(amount));
+    to_u64(U256::from(tokens).saturating_sub(new_tokens))
+}
+
+fn cp_cost(virtual_stable: u64, virtual_tokens: u64, supply: u64, tokens: u64) -> Option<u64> {
+    let (stable, _) = cp_reserves(virtual_stable, virtual_tokens, supply)?;
+    let (new_stable, _) = cp_reserves(virtual_stable, virtual_tokens, supply.checked_add(tokens)?)?;
+    to_u64(new_stable - stable)
+}
+
+fn cp_sell(virtual_stable: u64, virtual_tokens: u64, supply: u64, amount: u64) -> Option<u64> {
+    let (stable, _) = cp_reserves(virtual_stable, virtual_tokens, supply)?;
+    let (new_stable, _) = cp_reserves(virtual_stable, virtual_tokens, supply - amount)?;
+    to_u64(stable - new_stable)
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::instructions::schedule::ScheduleRound};
+
+    fn schedule() -> RoundSchedule {
+        RoundSchedule {
+            state: Default::default(),
+            rounds: vec![
+                ScheduleRound {
+                    end_supply: 3 * ROUND_VOLUME,
+                    price: 2,
+                },
+                ScheduleRound {
+                    end_supply: 5 * ROUND_VOLUME + 9,
+                    price: 5,
+                },
+                ScheduleRound {
+                    end_supply: 500 * ROUND_VOLUME,
+                    price: 5,
+                },
+                ScheduleRound {
+                    end_supply: MAX_TOTAL_SUPPLY,
+                    price: 40,
+                },
+            ],
+        }
+    }
+
+    fn curves() -> Vec<CurveConfig> {
+        vec![
+            CurveConfig {
+                kind: CurveKind::SteppedLinear,
+                param_a: 0,
+                param_b: 0,
+            },
+            CurveConfig {
+                kind: CurveKind::Exponential,
+                param_a: 1_000,
+                param_b: 20_000,
+            },
+            CurveConfig {
+                kind: CurveKind::ContinuousLinear,
+                param_a: 1_000,
+                param_b: 3,
+            },
+            CurveConfig {
+                kind: CurveKind::ConstantProduct,
+                param_a: 30_000_000_000,
+                param_b: 1_073_000_000 * TOKEN_UNIT,
+            },
+            CurveConfig {
+                kind: CurveKind::Schedule,
+                param_a: 0,
+                param_b: 0,
+            },
+        ]
+    }
+
+    const SUPPLIES: [u64; 4] = [0, 123_456_789, 5 * ROUND_VOLUME, 37 * ROUND_VOLUME + 17];
+    const AMOUNTS: [u64; 4] = [1_000, 2_500_000, 1_000_000_000, 10_000_000_000_000];
+
+    #[test]
+    fn test_round_trip_never_profits() {
+        let schedule = schedule();
+        for curve in curves() {
+            for supply in SUPPLIES {
+                for amount in AMOUNTS {
+                    let (out, _) = curve
+                        .buy(Some(&schedule), supply, amount, FEE_PERCENT_POINT)
+                        .unwrap();
+                    let (back, _) = curve.sell(Some(&schedule), supply + out, out).unwrap();
+                    assert!(back <= amount, "{:?} {} {}", curve.kind, supply, amount);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_more_stable_buys_more_tokens() {
+        let schedule = schedule();
+        for curve in curves() {
+            for supply in SUPPLIES {
+                let mut last = 0;
+                for amount in AMOUNTS {
+                    let (out, _) = curve
+                        .buy(Some(&schedule), supply, amount, FEE_PERCENT_POINT)
+                        .unwrap();
+                    assert!(out >= last, "{:?} {}", curve.kind, supply);
+                    last = out;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_spot_price_is_monotone() {
+        let schedule = schedule();
+        for curve in curves() {
+            let mut last = 0;
+            for supply in SUPPLIES {
+                let price = curve.spot_price(Some(&schedule), supply).unwrap();
+                assert!(price >= last, "{:?} {}", curve.kind, supply);
+                last = price;
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_buy_is_not_cheaper() {
+        let schedule = schedule();
+        for curve in curves() {
+            for supply in SUPPLIES {
+                let (whole, _) = curve
+                    .buy(Some(&schedule), supply, 2_000_000_000, FEE_PERCENT_POINT)
+                    .unwrap();
+                let (first, _) = curve
+                    .buy(Some(&schedule), supply, 1_000_000_000, FEE_PERCENT_POINT)
+                    .unwrap();
+                let (second, _) = curve
+                    .buy(
+                        Some(&schedule),
+                        supply + first,
+                        1_000_000_000,
+                        FEE_PERCENT_POINT,
+                    )
+                    .unwrap();
+                assert!(first + second <= whole + 1, "{:?} {}", curve.kind, supply);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cannot_sell_more_than_supply() {
+        let schedule = schedule();
+        for curve in curves() {
+            assert_eq!(
+                curve.sell(Some(&schedule), 10, 11),
+                Err(TokenError::CurveSupplyExceeded.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        assert_eq!(schedule().validate(), Ok(()));
+
+        let mut falling = schedule();
+        falling.rounds[1].price = 1;
+        assert_eq!(falling.validate(), Err(TokenError::InvalidSchedule.into()));
+
+        let mut overlapping = schedule();
+        overlapping.rounds[2].end_supply = overlapping.rounds[1].end_supply;
+        assert_eq!(
+            overlapping.validate(),
+            Err(TokenError::InvalidSchedule.into())
+        );
+    }
+
+    #[test]
+    fn test_schedule_buy_walks_rounds() {
+        let schedule = schedule();
+        // Whole first round at price 2, then half a round at price 5.
+        let cost = 3 * ROUND_VOLUME * 2 / TOKEN_UNIT + ROUND_VOLUME / 2 * 5 / TOKEN_UNIT;
+        assert_eq!(
+            schedule.buy(0, cost),
+            Ok(3 * ROUND_VOLUME + ROUND_VOLUME / 2)
+        );
+        assert_eq!(
+            schedule.sell(
+                3 * ROUND_VOLUME + ROUND_VOLUME / 2,
+                3 * ROUND_VOLUME + ROUND_VOLUME / 2
+            ),
+            Ok(cost)
+        );
+        assert_eq!(schedule.buy(schedule.max_supply(), 1), Ok(0));
+    }
+
+    #[test]
+    fn test_exact_tokens_cost_buys_them() {
+        let schedule = schedule();
+        for curve in curves() {
+            for supply in SUPPLIES {
+                for tokens in [1, 12_345, ROUND_VOLUME, 3 * ROUND_VOLUME + 7] {
+                    let (in_amount, fee) = curve
+                        .stable_for_exact_tokens(Some(&schedule), supply, tokens, FEE_PERCENT_POINT)
+                        .unwrap();
+                    let (out, buy_fee) = curve
+                        .buy(Some(&schedule), supply, in_amount, FEE_PERCENT_POINT)
+                        .unwrap();
+                    assert!(out + 1 >= tokens, "{:?} {} {}", curve.kind, supply, tokens);
+                    assert!(fee.abs_diff(buy_fee) <= 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounded_curves_stop_at_max_supply() {
+        let schedule = schedule();
+        for curve in curves() {
+            let max_supply = curve.max_supply(Some(&schedule)).unwrap();
+            assert!(max_supply <= MAX_TOTAL_SUPPLY);
+            if matches!(curve.kind, CurveKind::Schedule | CurveKind::ConstantProduct) {
+                let (out, _) = curve
+                    .buy(
+                        Some(&schedule),
+                        max_supply - 10,
+                        10_000_000_000_000,
+                        FEE_PERCENT_POINT,
+                    )
+                    .unwrap();
+                assert!(out <= 10, "{:?}", curve.kind);
+            }
+        }
+    }
+}
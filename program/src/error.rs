@@ -34,6 +34,189 @@ pub enum TokenError {
 
     #[error("WRONG mint_account")]
     WRONG_mint_account,
+
+    #[error("Wrong state account")]
+    WrongStateAccount,
+
+    #[error("Amount exceeds curve supply")]
+    CurveSupplyExceeded,
+
+    #[error("Invalid curve parameters")]
+    InvalidCurve,
+
+    #[error("Math overflow")]
+    MathOverflow,
+
+    #[error("Invalid round schedule")]
+    InvalidSchedule,
+
+    #[error("Wrong schedule account")]
+    WrongScheduleAccount,
+
+    #[error("Sale completed")]
+    SaleCompleted,
+
+    #[error("Launch can not graduate")]
+    NotGraduatable,
+
+    #[error("Wrong pool account")]
+    WrongPoolAccount,
+
+    #[error("Invalid presale parameters")]
+    InvalidPresale,
+
+    #[error("Presale in progress")]
+    PresaleActive,
+
+    #[error("Presale failed")]
+    PresaleFailed,
+
+    #[error("Refund unavailable")]
+    RefundUnavailable,
+
+    #[error("Wrong contribution account")]
+    WrongContributionAccount,
+
+    #[error("Invalid trading window")]
+    InvalidTradingWindow,
+
+    #[error("Trading not started")]
+    TradingNotStarted,
+
+    #[error("Sells not enabled")]
+    SellsNotEnabled,
+
+    #[error("Trading already started")]
+    TradingStarted,
+
+    #[error("Invalid allowlist parameters")]
+    InvalidAllowlist,
+
+    #[error("Allowlist phase closed")]
+    AllowlistClosed,
+
+    #[error("Invalid allowlist proof")]
+    InvalidProof,
+
+    #[error("Allowlist allocation exceeded")]
+    AllowlistAllocationExceeded,
+
+    #[error("Wrong allowlist account")]
+    WrongAllowlistAccount,
+
+    #[error("Wallet cap exceeded")]
+    WalletCapExceeded,
+
+    #[error("Price impact too high")]
+    PriceImpactTooHigh,
+
+    #[error("Wrong wallet account")]
+    WrongWalletAccount,
+
+    #[error("Invalid launch fee decay")]
+    InvalidFeeDecay,
+
+    #[error("Wrong stats account")]
+    WrongStatsAccount,
+
+    #[error("Wrong oracle account")]
+    WrongOracleAccount,
+
+    #[error("Oracle already initialized")]
+    OracleInitialized,
+
+    #[error("No observation old enough for the window")]
+    TwapUnavailable,
+
+    #[error("Wrong candles account")]
+    WrongCandlesAccount,
+
+    #[error("Candles already initialized")]
+    CandlesInitialized,
+
+    #[error("Invalid candle interval")]
+    InvalidCandleInterval,
+
+    #[error("Invalid referral share")]
+    InvalidReferralShare,
+
+    #[error("Wrong referral account")]
+    WrongReferralAccount,
+
+    #[error("Traders can not refer themselves")]
+    InvalidReferrer,
+
+    #[error("Nothing to claim")]
+    NothingToClaim,
+
+    #[error("Invalid creator fee")]
+    InvalidCreatorFee,
+
+    #[error("Wrong config account")]
+    WrongConfigAccount,
+
+    #[error("Config already initialized")]
+    ConfigInitialized,
+
+    #[error("Invalid config")]
+    InvalidConfig,
+
+    #[error("Trade token not allowed")]
+    TradeTokenNotAllowed,
+
+    #[error("Wrong registry account")]
+    WrongRegistryAccount,
+
+    #[error("Mint already exists")]
+    MintAlreadyExists,
+
+    #[error("Wrong metadata account")]
+    WrongMetadataAccount,
+
+    #[error("Wrong denylist account")]
+    WrongDenylistAccount,
+
+    #[error("Wrong holder token account")]
+    WrongHolderAccount,
+
+    #[error("Holder is on the denylist")]
+    HolderDenied,
+
+    #[error("Holder is not on the denylist")]
+    HolderNotDenied,
+
+    #[error("Invalid vesting")]
+    InvalidVesting,
+
+    #[error("Wrong vesting account")]
+    WrongVestingAccount,
+
+    #[error("Sells can not take the curve below the pre-allocation")]
+    BelowPreallocation,
+
+    #[error("Invalid buyback")]
+    InvalidBuyback,
+
+    #[error("Buyback unavailable")]
+    BuybackUnavailable,
+
+    #[error("Buyback ran too recently")]
+    BuybackTooSoon,
+
+    #[error("Invalid staking fee")]
+    InvalidStakingFee,
+
+    #[error("Wrong stake account")]
+    WrongStakeAccount,
+
+    #[error("Stake amount must be above zero and at most the stake")]
+    ZeroStake,
+
+    #[error("Wrong program data account")]
+    WrongProgramDataAccount,
+
+    #[error("Payout exceeds the launch reserve")]
+    ReserveExceeded,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
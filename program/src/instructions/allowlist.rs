@@ -0,0 +1,319 @@
+use super::{
+    create::{create_pda_account, LaunchStatus, State},
+    trade::_mintTokensForExactStable,
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, hash::hashv,
+        program_error::ProgramError, pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct MintWithProofArgs {
+    pub in_amount: u64,
+    pub out_not_less: u64,
+    /// Stable the wallet may spend during the allowlist phase, as in its leaf.
+    pub allocation: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Leaves and inner nodes are hashed with different prefixes so a node can
+/// not be passed off as a leaf.
+pub fn leaf_hash(wallet: &Pubkey, allocation: u64) -> [u8; 32] {
+    hashv(&[&[0], wallet.as_ref(), &allocation.to_le_bytes()]).to_bytes()
+}
+
+/// Pairs are sorted before hashing, so proofs carry no left/right flags.
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1], left, right]).to_bytes()
+}
+
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| node_hash(&node, sibling))
+        == *root
+}
+
+/// Stable spent by one wallet during the allowlist phase, stored at
+/// `[b"allowlist", state_account, wallet]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllowlistEntry {
+    pub state: Pubkey,
+    pub wallet: Pubkey,
+    pub used: u64,
+}
+
+impl AllowlistEntry {
+    pub const LEN: usize = 32 + 32 + 8;
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"allowlist", state_account.as_ref(), wallet.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Checks the phase and the proof of `wallet`, then reads its entry,
+    /// creating it on the first buy.
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_checked<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        account_state: &State,
+        wallet: &AccountInfo<'a>,
+        entry_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        now: i64,
+        args: &MintWithProofArgs,
+    ) -> Result<AllowlistEntry, ProgramError> {
+        if account_state.allowlist_root == [0; 32]
+            || account_state.status == LaunchStatus::Graduated
+            || now < account_state.allowlist_starts_at
+            || now >= account_state.trading_starts_at
+        {
+            return Err(TokenError::AllowlistClosed.into());
+        }
+        if !verify_proof(
+            &account_state.allowlist_root,
+            leaf_hash(wallet.key, args.allocation),
+            &args.proof,
+        ) {
+            return Err(TokenError::InvalidProof.into());
+        }
+
+        let (address, bump) = AllowlistEntry::address(program_id, state_account.key, wallet.key);
+        if address != *entry_account.key {
+            return Err(TokenError::WrongAllowlistAccount.into());
+        }
+        if entry_account.owner == program_id {
+            return AllowlistEntry::try_from_slice(&entry_account.data.borrow())
+                .map_err(|_| TokenError::WrongAllowlistAccount.into());
+        }
+        create_pda_account(
+            wallet,
+            entry_account,
+            AllowlistEntry::LEN,
+            program_id,
+            system_program,
+            &[
+                b"allowlist",
+                state_account.key.as_ref(),
+                wallet.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(AllowlistEntry {
+            state: *state_account.key,
+            wallet: *wallet.key,
+            used: 0,
+        })
+    }
+}
+
+/// `mintTokensForExactStableWithSlippage` for allowlisted wallets, open from
+/// `allowlist_starts_at` until `trading_starts_at`. Same accounts, followed by
+/// the wallet's allowlist entry.
+pub fn mint_with_proof(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: MintWithProofArgs,
+) -> ProgramResult {
+    _mintTokensForExactStable(
+        program_id,
+        accounts,
+        args.in_amount,
+        args.out_not_less,
+        Some(&args),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction};
+
+    /// Builds the tree the way a launch operator would off-chain. An odd node
+    /// is carried up to the next level unchanged.
+    fn build_tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+        let mut proofs = vec![vec![]; leaves.len()];
+        let mut positions: Vec<usize> = (0..leaves.len()).collect();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+                if let Some(sibling) = level.get(*position ^ 1) {
+                    proof.push(*sibling);
+                }
+                *position /= 2;
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node_hash(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        (level[0], proofs)
+    }
+
+    #[test]
+    fn test_proofs_verify_only_their_leaf() {
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = wallets
+            .iter()
+            .enumerate()
+            .map(|(i, wallet)| leaf_hash(wallet, 1_000 * (i as u64 + 1)))
+            .collect();
+        let (root, proofs) = build_tree(&leaves);
+
+        for (i, wallet) in wallets.iter().enumerate() {
+            let allocation = 1_000 * (i as u64 + 1);
+            assert!(verify_proof(
+                &root,
+                leaf_hash(wallet, allocation),
+                &proofs[i]
+            ));
+            assert!(!verify_proof(
+                &root,
+                leaf_hash(wallet, allocation + 1),
+                &proofs[i]
+            ));
+            assert!(!verify_proof(
+                &root,
+                leaf_hash(&Pubkey::new_unique(), allocation),
+                &proofs[i]
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn allowlist_buys_before_public_trading() {
+        // Far after the test clock, reached with `warp_to`.
+        let starts_at = i64::MAX / 4;
+        let allocation = 3_000_000;
+        let mut launch = TestLaunch::new().await;
+        let leaves = [
+            leaf_hash(&Pubkey::new_unique(), allocation),
+            leaf_hash(&launch.payer(), allocation),
+            leaf_hash(&Pubkey::new_unique(), allocation),
+        ];
+        let (root, proofs) = build_tree(&leaves);
+        let mut args = create_args(stepped_linear());
+        args.trading_starts_at = starts_at;
+        args.allowlist_root = root;
+        launch.create(args).await;
+
+        let (entry, _) =
+            AllowlistEntry::address(&launch.program_id, &launch.state, &launch.payer());
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(entry, false));
+        let buy_with_proof = |in_amount, allocation| {
+            SplMinterIntstruction::MintTokensForExactStableWithProof(MintWithProofArgs {
+                in_amount,
+                out_not_less: 0,
+                allocation,
+                proof: proofs[1].clone(),
+            })
+        };
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 1_000_000,
+            })
+        };
+
+        let result = launch.process(buy(), launch.mint_accounts()).await;
+        assert_token_error(result, TokenError::TradingNotStarted);
+        let result = launch
+            .process(buy_with_proof(1_000_000, allocation * 2), accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::InvalidProof);
+
+        launch
+            .process(buy_with_proof(2_000_000, allocation), accounts.clone())
+            .await
+            .unwrap();
+        // The second buy is filled up to the allocation.
+        launch
+            .process(buy_with_proof(2_000_000, allocation), accounts.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            PAYER_STABLE - allocation
+        );
+        let result = launch
+            .process(buy_with_proof(1_000_000, allocation), accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::AllowlistAllocationExceeded);
+
+        launch.warp_to(starts_at).await;
+        let result = launch
+            .process(buy_with_proof(1_000_000, allocation), accounts)
+            .await;
+        assert_token_error(result, TokenError::AllowlistClosed);
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn prefunded_allowlist_entry_does_not_block_buys() {
+        let starts_at = i64::MAX / 4;
+        let allocation = 3_000_000;
+        let mut launch = TestLaunch::new().await;
+        let leaves = [
+            leaf_hash(&launch.payer(), allocation),
+            leaf_hash(&Pubkey::new_unique(), allocation),
+        ];
+        let (root, proofs) = build_tree(&leaves);
+        let mut args = create_args(stepped_linear());
+        args.trading_starts_at = starts_at;
+        args.allowlist_root = root;
+        launch.create(args).await;
+
+        let payer = launch.payer();
+        let (entry, _) = AllowlistEntry::address(&launch.program_id, &launch.state, &payer);
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        launch
+            .send(
+                &[system_instruction::transfer(&payer, &entry, lamports)],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(entry, false));
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStableWithProof(MintWithProofArgs {
+                    in_amount: 1_000_000,
+                    out_not_less: 0,
+                    allocation,
+                    proof: proofs[0].clone(),
+                }),
+                accounts,
+            )
+            .await
+            .unwrap();
+        let stored = launch.account(entry).await;
+        assert_eq!(stored.owner, launch.program_id);
+        let stored = AllowlistEntry::try_from_slice(&stored.data).unwrap();
+        assert_eq!(stored.wallet, payer);
+        assert_eq!(stored.used, 1_000_000);
+    }
+}
@@ -0,0 +1,181 @@
+use super::{
+    create::{CreateTokenArgs, LaunchStatus, State},
+    creator::BPS_DENOMINATOR,
+    schedule::next_schedule,
+    trade::mul_div_u64,
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+pub fn validate_buyback(args: &CreateTokenArgs) -> ProgramResult {
+    if args.buyback_bps > BPS_DENOMINATOR || args.buyback_interval < 0 {
+        return Err(TokenError::InvalidBuyback.into());
+    }
+    Ok(())
+}
+
+impl State {
+    /// Stable the next `Buyback` spends: `buyback_bps` of the fees the claim
+    /// authority could still claim, at most `buyback_cap`.
+    pub fn buyback_budget(&self) -> u64 {
+        let unclaimed = self.total - self.protocol_fees() - self.claimed;
+        let budget = mul_div_u64(unclaimed, self.buyback_bps, BPS_DENOMINATOR).unwrap_or(0);
+        if self.buyback_cap > 0 {
+            budget.min(self.buyback_cap)
+        } else {
+            budget
+        }
+    }
+}
+
+/// Spends part of the accrued fees buying on the curve and burns what it buys.
+/// Fees and the reserve share the vault, so the buy only moves stable from
+/// `total` to `reserve`, and the bought tokens are never minted. Anyone can
+/// call it, once per `buyback_interval`. Accounts: state, then the round
+/// schedule for `CurveKind::Schedule`.
+pub fn buyback(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let state_account = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let now = Clock::get()?.unix_timestamp;
+    account_state.settle_presale(now);
+    if account_state.buyback_bps == 0
+        || account_state.in_presale()
+        || !matches!(
+            account_state.status,
+            LaunchStatus::Active | LaunchStatus::Succeeded
+        )
+    {
+        return Err(TokenError::BuybackUnavailable.into());
+    }
+    if account_state.last_buyback_at > 0
+        && now < account_state.last_buyback_at + account_state.buyback_interval
+    {
+        return Err(TokenError::BuybackTooSoon.into());
+    }
+    let schedule = next_schedule(program_id, state_account, &account_state, accounts_iter)?;
+
+    let supply = account_state.curve_supply;
+    let mut spent = account_state.buyback_budget();
+    let (mut burned, _) = account_state
+        .curve
+        .buy(schedule.as_ref(), supply, spent, 0)?;
+    let mut max_supply = account_state.curve.max_supply(schedule.as_ref())?;
+    if account_state.graduation_threshold > 0 {
+        max_supply = max_supply.min(account_state.graduation_threshold);
+    }
+    let remaining = max_supply.saturating_sub(supply);
+    if burned >= remaining {
+        let (fill, _) =
+            account_state
+                .curve
+                .stable_for_exact_tokens(schedule.as_ref(), supply, remaining, 0)?;
+        burned = remaining;
+        spent = fill.min(spent);
+        account_state.complete_sale(max_supply);
+    }
+    if burned == 0 {
+        return Err(TokenError::ZeroBuy.into());
+    }
+
+    account_state.claimed += spent;
+    account_state.reserve += spent;
+    account_state.curve_supply += burned;
+    account_state.buyback_spent += spent;
+    account_state.buyback_burned += burned;
+    account_state.last_buyback_at = now;
+    if let Ok(price) = account_state
+        .curve
+        .spot_price(schedule.as_ref(), account_state.curve_supply)
+    {
+        let round = account_state
+            .curve
+            .round(schedule.as_ref(), account_state.curve_supply)?;
+        account_state.track_price(price, round);
+        account_state.update_price(now, price);
+    }
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    msg!("Buyback {} {} {}", state_account.key, spent, burned);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    #[tokio::test]
+    async fn buyback_spends_fees_and_burns_supply() {
+        let mut args = create_args(stepped_linear());
+        args.buyback_bps = 5_000;
+        args.buyback_interval = 60;
+        let mut launch = TestLaunch::start(args).await;
+        let crank = vec![AccountMeta::new(launch.state, false)];
+        let result = launch
+            .process(SplMinterIntstruction::Buyback, crank.clone())
+            .await;
+        assert_token_error(result, TokenError::ZeroBuy);
+
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 100_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        let before = launch.state().await;
+        launch
+            .process(SplMinterIntstruction::Buyback, crank.clone())
+            .await
+            .unwrap();
+        let after = launch.state().await;
+        let spent = before.total / 2;
+        assert_eq!(after.buyback_spent, spent);
+        assert_eq!(after.claimed, spent);
+        assert_eq!(after.reserve, before.reserve + spent);
+        assert!(after.buyback_burned > 0);
+        assert_eq!(
+            after.curve_supply,
+            before.curve_supply + after.buyback_burned
+        );
+        assert!(after.last_price >= before.last_price);
+        // Burned tokens were never minted.
+        assert_eq!(launch.mint_supply().await, after.minted_supply());
+
+        let result = launch
+            .process(SplMinterIntstruction::Buyback, crank.clone())
+            .await;
+        assert_token_error(result, TokenError::BuybackTooSoon);
+        let now = launch.now().await;
+        launch.warp_to(now + 60).await;
+        launch
+            .process(SplMinterIntstruction::Buyback, crank)
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.state().await.buyback_spent,
+            spent + (before.total - spent) / 2
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use super::create::{create_pda_account, State};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct InitCandlesArgs {
+    /// Seconds covered by one candle.
+    pub interval: i64,
+}
+
+/// Spot prices after the trades of one interval, in stable units per whole
+/// token.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Candle {
+    /// Start of the interval, 0 for an entry not written yet.
+    pub start: i64,
+    pub open: u64,
+    pub high: u64,
+    pub low: u64,
+    pub close: u64,
+    /// Stable traded, both sides.
+    pub volume: u64,
+}
+
+/// Ring of the latest candles, stored at `[b"candles", state_account]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Candles {
+    pub state: Pubkey,
+    pub interval: i64,
+    /// Index the next candle overwrites.
+    pub next: u16,
+    pub candles: Vec<Candle>,
+}
+
+impl Candles {
+    pub const CAPACITY: usize = 128;
+    pub const LEN: usize = 32 + 8 + 2 + 4 + Candles::CAPACITY * (8 + 8 * 5);
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"candles", state_account.as_ref()], program_id)
+    }
+
+    /// Reads a candles account fetched off-chain.
+    pub fn decode(data: &[u8]) -> Result<Candles, ProgramError> {
+        Candles::try_from_slice(data).map_err(|_| TokenError::WrongCandlesAccount.into())
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        candles_account: &AccountInfo,
+    ) -> Result<Candles, ProgramError> {
+        let (address, _) = Candles::address(program_id, state_account.key);
+        if address != *candles_account.key || candles_account.owner != program_id {
+            return Err(TokenError::WrongCandlesAccount.into());
+        }
+        Candles::decode(&candles_account.data.borrow())
+    }
+
+    /// Written candles, oldest first.
+    pub fn history(&self) -> Vec<Candle> {
+        let (newer, older) = self.candles.split_at(self.next as usize);
+        older
+            .iter()
+            .chain(newer)
+            .filter(|candle| candle.start > 0)
+            .copied()
+            .collect()
+    }
+
+    fn latest_mut(&mut self) -> &mut Candle {
+        let index = (self.next as usize + Candles::CAPACITY - 1) % Candles::CAPACITY;
+        &mut self.candles[index]
+    }
+
+    /// Adds a trade moving the spot price from `open` to `price`.
+    pub fn record(&mut self, now: i64, open: u64, price: u64, volume: u64) {
+        let start = now - now.rem_euclid(self.interval);
+        let latest = self.latest_mut();
+        if latest.start == start {
+            latest.high = latest.high.max(price);
+            latest.low = latest.low.min(price);
+            latest.close = price;
+            latest.volume += volume;
+            return;
+        }
+        self.candles[self.next as usize] = Candle {
+            start,
+            open,
+            high: open.max(price),
+            low: open.min(price),
+            close: price,
+            volume,
+        };
+        self.next = ((self.next as usize + 1) % Candles::CAPACITY) as u16;
+    }
+}
+
+/// Reads the candles account trades pass once `InitCandles` ran.
+pub fn next_candles<'a, 'b>(
+    program_id: &Pubkey,
+    state_account: &AccountInfo<'a>,
+    account_state: &State,
+    accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+) -> Result<Option<(&'b AccountInfo<'a>, Candles)>, ProgramError> {
+    if !account_state.candles_enabled {
+        return Ok(None);
+    }
+    let candles_account = next_account_info(accounts_iter)?;
+    let candles = Candles::load(program_id, state_account, candles_account)?;
+    Ok(Some((candles_account, candles)))
+}
+
+/// Adds a trade of `volume` stable that moved the spot price from `open` to
+/// `last_price`, if the launch keeps candles.
+pub fn save_candles(
+    candles: Option<(&AccountInfo, Candles)>,
+    account_state: &State,
+    now: i64,
+    open: u64,
+    volume: u64,
+) -> ProgramResult {
+    if let Some((candles_account, mut candles)) = candles {
+        candles.record(now, open, account_state.last_price, volume);
+        candles.serialize(&mut &mut candles_account.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+/// Creates the candles account of a launch. From then on every trade has to
+/// pass it, right after the oracle.
+pub fn init_candles(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitCandlesArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let claim_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let candles_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !claim_account.is_signer || *claim_account.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if account_state.candles_enabled {
+        return Err(TokenError::CandlesInitialized.into());
+    }
+    if args.interval <= 0 {
+        return Err(TokenError::InvalidCandleInterval.into());
+    }
+    let (address, bump) = Candles::address(program_id, state_account.key);
+    if address != *candles_account.key {
+        return Err(TokenError::WrongCandlesAccount.into());
+    }
+
+    create_pda_account(
+        claim_account,
+        candles_account,
+        Candles::LEN,
+        program_id,
+        system_program,
+        &[b"candles", state_account.key.as_ref(), &[bump]],
+    )?;
+    let candles = Candles {
+        state: *state_account.key,
+        interval: args.interval,
+        next: 0,
+        candles: vec![Candle::default(); Candles::CAPACITY],
+    };
+    candles.serialize(&mut &mut candles_account.data.borrow_mut()[..])?;
+
+    account_state.candles_enabled = true;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+    msg!(
+        "Candles: {}, interval: {}",
+        candles_account.key,
+        args.interval
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs},
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_program};
+
+    #[tokio::test]
+    async fn trades_fill_candles() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let (candles, _) = Candles::address(&launch.program_id, &launch.state);
+        let init_accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(candles, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        let init = |interval| SplMinterIntstruction::InitCandles(InitCandlesArgs { interval });
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+        let sell = |amount| {
+            SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                _inAmount: amount,
+            })
+        };
+        let mut buy_accounts = launch.mint_accounts();
+        buy_accounts.push(AccountMeta::new(candles, false));
+        let mut sell_accounts = launch.burn_accounts();
+        sell_accounts.push(AccountMeta::new(candles, false));
+
+        let result = launch.process(init(0), init_accounts.clone()).await;
+        assert_token_error(result, TokenError::InvalidCandleInterval);
+        launch.process(init(60), init_accounts).await.unwrap();
+
+        // Into round 2 and back within one minute, then a buy the next minute.
+        let start = launch.now().await / 60 * 60 + 60;
+        launch.warp_to(start).await;
+        launch
+            .process(buy(1_500_000), buy_accounts.clone())
+            .await
+            .unwrap();
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+        let balance = launch.token_balance(launch.payer_stable()).await;
+        launch.warp_to(start + 30).await;
+        launch
+            .process(sell(tokens), sell_accounts.clone())
+            .await
+            .unwrap();
+        let sold = launch.token_balance(launch.payer_stable()).await - balance;
+        launch.warp_to(start + 90).await;
+        launch.process(buy(500_000), buy_accounts).await.unwrap();
+
+        let history = Candles::decode(&launch.account(candles).await.data)
+            .unwrap()
+            .history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0],
+            Candle {
+                start,
+                open: 1,
+                high: 2,
+                low: 1,
+                close: 1,
+                volume: 1_500_000 + sold,
+            }
+        );
+        assert_eq!(
+            history[1],
+            Candle {
+                start: start + 60,
+                open: 1,
+                high: 1,
+                low: 1,
+                close: 1,
+                volume: 500_000,
+            }
+        );
+    }
+}
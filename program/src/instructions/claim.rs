@@ -1,16 +1,20 @@
-use super::create::State;
+use super::create::{vault_authority, State};
 use crate::error::TokenError;
 use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::Clock,
         entrypoint::ProgramResult,
         msg,
         program::{invoke, invoke_signed},
         pubkey::Pubkey,
+        sysvar::Sysvar,
 
     },
-    spl_associated_token_account::instruction as associated_token_account_instruction,
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
     spl_token::{instruction as token_instruction,  ID as spl_token_program_id},
 };
 
@@ -36,6 +40,9 @@ pub fn claim_token(
     let token_program = next_account_info(accounts_iter)?;
     let associated_token_program = next_account_info(accounts_iter)?;
 
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
     let mut account_state = State::try_from_slice(&state_account.data.borrow()).unwrap();
 
     if claim_account.is_signer {
@@ -45,8 +52,22 @@ pub fn claim_token(
     } else {
         return Err(TokenError::Unauthorized.into());
     };
+    let (pda_trade_token_ata_authority, bump_seed_2) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_ata_authority,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+    account_state.settle_presale(Clock::get()?.unix_timestamp);
+    account_state.check_presale_settled()?;
 
-    assert!(args.amount <= account_state.total - account_state.claimed);
+    assert!(
+        args.amount
+            <= account_state.total - account_state.protocol_fees() - account_state.claimed
+    );
     msg!(
         "amount: {}, total: {}, claimed: {}",
         args.amount,
@@ -79,9 +100,6 @@ pub fn claim_token(
         )?;
     };
 
-    let (pda_trade_token_ata_authority, bump_seed_2) =
-        Pubkey::find_program_address(&[b"ata_trade_token_authority"], &program_id);
-
     invoke_signed(
         &token_instruction::transfer(
             &spl_token_program_id,
@@ -96,8 +114,69 @@ pub fn claim_token(
             claim_account_trade_token_ata_account.clone(),
             pda_trade_token_ata_authority_info.clone(),
         ],
-        &[&[b"ata_trade_token_authority", &[bump_seed_2]]],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed_2]]],
     )?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_program};
+
+    #[tokio::test]
+    async fn claims_stay_in_their_launch_vault() {
+        let mut launch = TestLaunch::new().await;
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 1_000_000,
+            })
+        };
+        launch.create(create_args(stepped_linear())).await;
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+        let first_vault = launch.vault();
+        let first_stable = launch.payer_stable();
+
+        // Launches on the same stable token still get a vault each.
+        launch.create(create_args(stepped_linear())).await;
+        assert_ne!(launch.vault(), first_vault);
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+        let fees = launch.state().await.total;
+        let (payer, vault_authority, stable_mint) =
+            (launch.payer(), launch.vault_authority(), launch.stable_mint);
+        let claim_accounts = |state, vault, destination| {
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(state, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(vault_authority, false),
+                AccountMeta::new_readonly(stable_mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            ]
+        };
+        let claim = || SplMinterIntstruction::Claim(ClaimArgs { amount: fees });
+
+        let accounts = claim_accounts(launch.state, first_vault, first_stable);
+        let result = launch.process(claim(), accounts).await;
+        assert_token_error(result, TokenError::WRONG_pda_trade_token_ata_account);
+        // A state the program does not own can not vouch for any fees.
+        let accounts = claim_accounts(first_stable, first_vault, first_stable);
+        let result = launch.process(claim(), accounts).await;
+        assert_token_error(result, TokenError::WrongStateAccount);
+
+        let accounts = claim_accounts(launch.state, launch.vault(), launch.payer_stable());
+        launch.process(claim(), accounts).await.unwrap();
+        assert_eq!(
+            launch.token_balance(launch.vault()).await,
+            launch.state().await.reserve
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use super::{
+    claim::ClaimArgs,
+    create::{_create_token, create_pda_account, vault_authority, CreateTokenArgs, State},
+    creator::BPS_DENOMINATOR,
+    trade::mul_div_u64,
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        program_utils::limited_deserialize,
+        pubkey::Pubkey,
+        system_instruction,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+pub const MAX_TRADE_TOKENS: usize = 16;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ConfigArgs {
+    pub admin: Pubkey,
+    /// Wallet receiving the creation fee.
+    pub treasury: Pubkey,
+    /// Part of each launch's trade fees owed to the protocol, in basis points.
+    pub protocol_fee_bps: u64,
+    /// Lamports charged by `CreateLaunch`.
+    pub creation_fee: u64,
+    /// Stable mints launches may trade against, at most `MAX_TRADE_TOKENS`.
+    pub trade_tokens: Vec<Pubkey>,
+}
+
+/// Launchpad settings, stored at `[b"config"]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub protocol_fee_bps: u64,
+    pub creation_fee: u64,
+    /// Launches made through `CreateLaunch`.
+    pub launch_count: u64,
+    /// Allowed trade tokens, padded with `Pubkey::default()`.
+    pub trade_tokens: Vec<Pubkey>,
+}
+
+impl Config {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 4 + MAX_TRADE_TOKENS * 32;
+
+    pub fn address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], program_id)
+    }
+
+    pub fn load(program_id: &Pubkey, config_account: &AccountInfo) -> Result<Config, ProgramError> {
+        let (address, _) = Config::address(program_id);
+        if address != *config_account.key || config_account.owner != program_id {
+            return Err(TokenError::WrongConfigAccount.into());
+        }
+        Config::try_from_slice(&config_account.data.borrow())
+            .map_err(|_| TokenError::WrongConfigAccount.into())
+    }
+
+    fn apply(&mut self, args: ConfigArgs) -> ProgramResult {
+        if args.protocol_fee_bps > BPS_DENOMINATOR
+            || args.trade_tokens.len() > MAX_TRADE_TOKENS
+            || args.trade_tokens.contains(&Pubkey::default())
+        {
+            return Err(TokenError::InvalidConfig.into());
+        }
+        self.admin = args.admin;
+        self.treasury = args.treasury;
+        self.protocol_fee_bps = args.protocol_fee_bps;
+        self.creation_fee = args.creation_fee;
+        self.trade_tokens = args.trade_tokens;
+        self.trade_tokens
+            .resize(MAX_TRADE_TOKENS, Pubkey::default());
+        Ok(())
+    }
+
+    pub fn allows(&self, trade_token: &Pubkey) -> bool {
+        *trade_token != Pubkey::default() && self.trade_tokens.contains(trade_token)
+    }
+}
+
+/// Errors unless launches may trade against `trade_token`: any token goes
+/// until `InitConfig` ran, then only allowed ones.
+pub fn check_trade_token(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    trade_token: &Pubkey,
+) -> ProgramResult {
+    let (address, _) = Config::address(program_id);
+    if address != *config_account.key {
+        return Err(TokenError::WrongConfigAccount.into());
+    }
+    if config_account.owner != program_id {
+        return Ok(());
+    }
+    if !Config::load(program_id, config_account)?.allows(trade_token) {
+        return Err(TokenError::TradeTokenNotAllowed.into());
+    }
+    Ok(())
+}
+
+/// Launch-wide settings `CreateLaunch` copies into the new `State`.
+pub struct LaunchRegistration {
+    pub config: Pubkey,
+    pub launch_id: u64,
+    pub protocol_fee_bps: u64,
+}
+
+impl State {
+    /// Part of `total` owed to the protocol.
+    pub fn protocol_fees(&self) -> u64 {
+        mul_div_u64(self.total, self.protocol_fee_bps, BPS_DENOMINATOR).unwrap_or(0)
+    }
+}
+
+/// Upgrade authority stored in the `ProgramData` account of `program_id`,
+/// `None` once the program was made immutable.
+fn upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+) -> Result<Option<Pubkey>, ProgramError> {
+    let (address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if address != *program_data.key || *program_data.owner != bpf_loader_upgradeable::id() {
+        return Err(TokenError::WrongProgramDataAccount.into());
+    }
+    match limited_deserialize(
+        &program_data.data.borrow(),
+        UpgradeableLoaderState::size_of_programdata_metadata() as u64,
+    ) {
+        Ok(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        }) => Ok(upgrade_authority_address),
+        _ => Err(TokenError::WrongProgramDataAccount.into()),
+    }
+}
+
+/// Creates the launchpad config. Only the program's upgrade authority may,
+/// and it picks the admin. Accounts: upgrade authority (pays), config,
+/// system, the program's `ProgramData`.
+pub fn init_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ConfigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let program_data = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer || upgrade_authority(program_id, program_data)? != Some(*authority.key)
+    {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let (address, bump) = Config::address(program_id);
+    if address != *config_account.key {
+        return Err(TokenError::WrongConfigAccount.into());
+    }
+    if config_account.owner == program_id {
+        return Err(TokenError::ConfigInitialized.into());
+    }
+    let mut config = Config {
+        admin: Pubkey::default(),
+        treasury: Pubkey::default(),
+        protocol_fee_bps: 0,
+        creation_fee: 0,
+        launch_count: 0,
+        trade_tokens: vec![],
+    };
+    config.apply(args)?;
+
+    create_pda_account(
+        authority,
+        config_account,
+        Config::LEN,
+        program_id,
+        system_program,
+        &[b"config", &[bump]],
+    )?;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    msg!("Config: {}, admin: {}", config_account.key, config.admin);
+    Ok(())
+}
+
+/// Replaces every setting, the admin included. Accounts: admin, config.
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ConfigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    if !admin.is_signer || *admin.key != config.admin {
+        return Err(TokenError::Unauthorized.into());
+    }
+    config.apply(args)?;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    msg!("Config updated, admin: {}", config.admin);
+    Ok(())
+}
+
+/// `Create` through the launchpad. Accounts: config, treasury, then those of
+/// `Create` with the state at `[b"state", mint]`. Charges the creation fee
+/// and numbers the launch.
+pub fn create_launch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateTokenArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let create_accounts = accounts_iter.as_slice();
+    let payer = create_accounts
+        .get(3)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let trade_token = create_accounts
+        .get(7)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let system_program = create_accounts
+        .get(9)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    if *treasury.key != config.treasury {
+        return Err(TokenError::WrongConfigAccount.into());
+    }
+    if !config.allows(trade_token.key) {
+        return Err(TokenError::TradeTokenNotAllowed.into());
+    }
+    if config.creation_fee > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, treasury.key, config.creation_fee),
+            &[payer.clone(), treasury.clone(), system_program.clone()],
+        )?;
+    }
+
+    let launch_id = config.launch_count;
+    config.launch_count += 1;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    _create_token(
+        program_id,
+        create_accounts,
+        args,
+        Some(LaunchRegistration {
+            config: *config_account.key,
+            launch_id,
+            protocol_fee_bps: config.protocol_fee_bps,
+        }),
+    )?;
+    msg!("Launch {}: {}", launch_id, create_accounts[4].key);
+    Ok(())
+}
+
+/// Pays the protocol its share of a launch's fees. Accounts: admin, config,
+/// state, vault, admin stable account, vault authority, stable mint, system,
+/// token and associated token programs.
+pub fn claim_protocol_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ClaimArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let admin_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let trade_token = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let config = Config::load(program_id, config_account)?;
+    if !admin.is_signer || *admin.key != config.admin {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if account_state.config != *config_account.key {
+        return Err(TokenError::WrongConfigAccount.into());
+    }
+    account_state.check_presale_settled()?;
+    if args.amount == 0
+        || args.amount > account_state.protocol_fees() - account_state.protocol_claimed
+    {
+        return Err(TokenError::NothingToClaim.into());
+    }
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+
+    account_state.protocol_claimed += args.amount;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    if admin_trade_token_ata_account.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                admin.key,
+                admin.key,
+                trade_token.key,
+                token_program.key,
+            ),
+            &[
+                trade_token.clone(),
+                admin_trade_token_ata_account.clone(),
+                admin.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            admin_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            args.amount,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            admin_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!(
+        "Protocol fees claimed {} {}",
+        state_account.key,
+        args.amount
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn launchpad_charges_registers_and_shares_fees() {
+        let mut launch = TestLaunch::new().await;
+        let (config, _) = Config::address(&launch.program_id);
+        let treasury = Pubkey::new_unique();
+        let admin = launch.payer();
+        let config_args = |trade_tokens| ConfigArgs {
+            admin,
+            treasury,
+            protocol_fee_bps: 2_000,
+            creation_fee: 100_000_000,
+            trade_tokens,
+        };
+        let program_data = launch.program_data();
+        let init_accounts = |authority| {
+            vec![
+                AccountMeta::new(authority, true),
+                AccountMeta::new(config, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(program_data, false),
+            ]
+        };
+        let update_accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(config, false),
+        ];
+        let init = SplMinterIntstruction::InitConfig(config_args(vec![]));
+        let update = SplMinterIntstruction::UpdateConfig(config_args(vec![launch.stable_mint]));
+        // Only the upgrade authority configures the launchpad, even when
+        // someone funded the config address first.
+        let outsider = Keypair::new();
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        launch
+            .send(
+                &[
+                    system_instruction::transfer(&admin, &outsider.pubkey(), 1_000_000_000),
+                    system_instruction::transfer(&admin, &config, lamports),
+                ],
+                &[],
+            )
+            .await
+            .unwrap();
+        let result = launch
+            .process_with_signers(
+                SplMinterIntstruction::InitConfig(config_args(vec![])),
+                init_accounts(outsider.pubkey()),
+                &[&outsider],
+            )
+            .await;
+        assert_token_error(result, TokenError::Unauthorized);
+        launch.process(init, init_accounts(admin)).await.unwrap();
+        let result = launch
+            .process(
+                SplMinterIntstruction::InitConfig(config_args(vec![])),
+                init_accounts(admin),
+            )
+            .await;
+        assert_token_error(result, TokenError::ConfigInitialized);
+        // Once configured, `Create` is held to the allowed trade tokens too.
+        let result = launch.try_create(create_args(stepped_linear())).await;
+        assert_token_error(result, TokenError::TradeTokenNotAllowed);
+
+        let mint = Keypair::new();
+        launch.mint = mint.pubkey();
+        launch.state =
+            Pubkey::find_program_address(&[b"state", launch.mint.as_ref()], &launch.program_id).0;
+        let mut args = create_args(stepped_linear());
+        args.claime_authority = launch.payer();
+        let mut launch_accounts = vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(treasury, false),
+        ];
+        launch_accounts.extend(launch.create_accounts().await);
+        launch_accounts[6] = AccountMeta::new(launch.state, false);
+        let create = |args| SplMinterIntstruction::CreateLaunch(args);
+        let result = launch
+            .process_with_signers(
+                create(create_args(stepped_linear())),
+                launch_accounts.clone(),
+                &[&mint],
+            )
+            .await;
+        assert_token_error(result, TokenError::TradeTokenNotAllowed);
+        launch.process(update, update_accounts).await.unwrap();
+        launch
+            .process_with_signers(create(args), launch_accounts, &[&mint])
+            .await
+            .unwrap();
+
+        assert_eq!(launch.account(treasury).await.lamports, 100_000_000);
+        let stored = Config::try_from_slice(&launch.account(config).await.data).unwrap();
+        assert_eq!(stored.launch_count, 1);
+        let state = launch.state().await;
+        assert_eq!((state.config, state.launch_id), (config, 0));
+        assert_eq!(state.protocol_fee_bps, 2_000);
+
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 1_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        let state = launch.state().await;
+        let protocol = state.protocol_fees();
+        assert_eq!(protocol, state.total / 5);
+
+        let claim = |amount| ClaimArgs { amount };
+        let claim_accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(launch.vault(), false),
+            AccountMeta::new(launch.payer_stable(), false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new_readonly(launch.stable_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let mut protocol_accounts = claim_accounts.clone();
+        protocol_accounts.insert(1, AccountMeta::new_readonly(config, false));
+        let owner_share = state.total - protocol;
+        launch
+            .process(
+                SplMinterIntstruction::Claim(claim(owner_share)),
+                claim_accounts,
+            )
+            .await
+            .unwrap();
+
+        let balance = launch.token_balance(launch.payer_stable()).await;
+        launch
+            .process(
+                SplMinterIntstruction::ClaimProtocolFees(claim(protocol)),
+                protocol_accounts.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await - balance,
+            protocol
+        );
+        let result = launch
+            .process(
+                SplMinterIntstruction::ClaimProtocolFees(claim(1)),
+                protocol_accounts,
+            )
+            .await;
+        assert_token_error(result, TokenError::NothingToClaim);
+    }
+}
@@ -1,3 +1,15 @@
+use super::{
+    buyback::validate_buyback,
+    config::{check_trade_token, LaunchRegistration},
+    creator::validate_creator_fee,
+    presale::validate_presale,
+    referral::validate_referral_share,
+    registry::{register_launch, LaunchRecord},
+    staking::validate_staking,
+    trading_window::{validate_fee_decay, validate_trading_window},
+    vesting::{create_vesting, validate_vesting, Vesting},
+};
+use crate::{curve::CurveConfig, error::TokenError};
 use {
     borsh::{BorshDeserialize, BorshSerialize},
     mpl_token_metadata::{instructions as mpl_instruction, types::DataV2},
@@ -8,6 +20,7 @@ use {
         program::{invoke, invoke_signed},
         program_pack::Pack,
         pubkey::Pubkey,
+        clock::Clock,
         rent::Rent,
         system_instruction,
         sysvar::Sysvar,
@@ -22,12 +35,149 @@ pub struct CreateTokenArgs {
     pub token_symbol: String,
     pub token_uri: String,
     pub claime_authority: Pubkey,
+    pub curve: CurveConfig,
+    /// Curve supply after which `Graduate` can be called, 0 to disable.
+    pub graduation_threshold: u64,
+    /// Tokens minted into the pool at graduation.
+    pub pool_token_allocation: u64,
+    /// Stable to raise before `deadline`, 0 to launch without a presale.
+    pub soft_cap: u64,
+    /// Stable at which the presale closes early, 0 for no cap.
+    pub hard_cap: u64,
+    /// Unix timestamp closing the presale.
+    pub deadline: i64,
+    /// Unix timestamp opening buys, 0 to open them right away.
+    pub trading_starts_at: i64,
+    /// Unix timestamp opening sells, 0 to open them with buys.
+    pub sells_enabled_at: i64,
+    /// Merkle root of `(wallet, allocation)` leaves, zero for no allowlist.
+    pub allowlist_root: [u8; 32],
+    /// Unix timestamp opening allowlist buys, before `trading_starts_at`.
+    pub allowlist_starts_at: i64,
+    /// Tokens one wallet may buy in total, 0 for no cap.
+    pub wallet_token_cap: u64,
+    /// Stable one wallet may spend in total, 0 for no cap.
+    pub wallet_stable_cap: u64,
+    /// Spot price rise allowed per buy, in `PERCENT_DENOMINATOR` units of a
+    /// percent. 0 for no limit.
+    pub max_price_impact: u64,
+    /// Buy fee at `trading_starts_at`, in the units of `FEE_PERCENT_POINT`.
+    /// 0 charges the normal fee from the start.
+    pub launch_fee_rate: u64,
+    /// Seconds over which the buy fee falls linearly to the normal fee.
+    pub fee_decay_period: i64,
+    /// Part of each trade fee owed to the trade's referrer, in
+    /// `PERCENT_DENOMINATOR` units of a percent, at most `MAX_REFERRAL_SHARE`.
+    pub referral_share: u64,
+    /// Wallet allowed to claim the creator royalty.
+    pub creator: Pubkey,
+    /// Royalty on every trade, in basis points, at most `MAX_CREATOR_FEE_BPS`.
+    pub creator_fee_bps: u64,
+    /// Derive the mint from the payer and `mint_nonce` (see `mint_address`)
+    /// instead of signing with a fresh keypair. The payer signs, so nobody
+    /// else can take the address first.
+    pub pda_mint: bool,
+    pub mint_nonce: u64,
+    /// Create the mint without a freeze authority. Otherwise the
+    /// `mint_authority` PDA holds it until `RevokeFreezeAuthority`.
+    pub no_freeze_authority: bool,
+    /// Tokens minted at creation into the vesting escrow, 0 for none. They
+    /// count as sold curve supply, so buys start priced above them, and the
+    /// payer pays their curve cost into the reserve, which then backs vested
+    /// tokens sold back.
+    pub vesting_amount: u64,
+    pub vesting_beneficiary: Pubkey,
+    /// Seconds after creation before anything can be claimed.
+    pub vesting_cliff: i64,
+    /// Seconds after creation until all of `vesting_amount` is vested.
+    pub vesting_duration: i64,
+    /// Part of the claimable fees each `Buyback` spends, in basis points.
+    pub buyback_bps: u64,
+    /// Seconds between two buybacks.
+    pub buyback_interval: i64,
+    /// Most stable a single buyback spends, 0 for no limit.
+    pub buyback_cap: u64,
+    /// Part of each trade fee shared with stakers, in basis points.
+    pub staking_fee_bps: u64,
+}
+
+/// Mint of a `pda_mint` launch. Each payer and nonce can launch once.
+pub fn mint_address(program_id: &Pubkey, payer: &Pubkey, mint_nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"mint", payer.as_ref(), &mint_nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Creates the account at a PDA of this program, owned by `owner` and paid by
+/// `payer`. Lamports someone sent to the address beforehand do not block it:
+/// the account is topped up to rent exemption, then allocated and assigned.
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                rent_lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+    let shortfall = rent_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Owner of the stable vault of one launch, stored at its associated token
+/// address for the trade token.
+pub fn vault_authority(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", state_account.as_ref()], program_id)
 }
 
+/// Accounts end with the associated token program, the registry, the
+/// registry page the launch falls in and the config, then the vesting
+/// account, its escrow and the payer's stable account for a launch with a
+/// pre-allocation.
 pub fn create_token(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     args: CreateTokenArgs,
+) -> ProgramResult {
+    _create_token(program_id, accounts, args, None)
+}
+
+/// `registration` is set for launches made through `CreateLaunch`, whose
+/// state lives at `[b"state", mint]`.
+pub(crate) fn _create_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateTokenArgs,
+    registration: Option<LaunchRegistration>,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -43,25 +193,59 @@ pub fn create_token(
     let system_program = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
     let token_metadata_program = next_account_info(accounts_iter)?;
+    let _associated_token_program = next_account_info(accounts_iter)?;
+    let registry_account = next_account_info(accounts_iter)?;
+    let registry_page_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
 
+    check_trade_token(program_id, config_account, trade_token_account.key)?;
+    args.curve.validate()?;
+    validate_vesting(&args)?;
+    validate_buyback(&args)?;
+    validate_staking(&args)?;
+    if args.graduation_threshold > 0 && args.pool_token_allocation == 0 {
+        return Err(TokenError::NotGraduatable.into());
+    }
+    validate_presale(&args)?;
+    validate_trading_window(args.trading_starts_at, args.sells_enabled_at)?;
+    if args.allowlist_root != [0; 32] && args.allowlist_starts_at >= args.trading_starts_at {
+        return Err(TokenError::InvalidAllowlist.into());
+    }
+    validate_fee_decay(args.launch_fee_rate, args.fee_decay_period)?;
+    validate_referral_share(args.referral_share)?;
+    validate_creator_fee(&args.creator, args.creator_fee_bps)?;
 
     // First create the account for the Mint
     //
     msg!("Creating mint account...");
     msg!("Mint: {}", mint_account.key);
-    invoke(
-        &system_instruction::create_account(
-            payer.key,
-            mint_account.key,
-            (Rent::get()?).minimum_balance(Mint::LEN),
-            Mint::LEN as u64,
-            token_program.key,
-        ),
+    let creator = if args.creator == Pubkey::default() {
+        *payer.key
+    } else {
+        args.creator
+    };
+    let (mint_pda, mint_bump) = mint_address(program_id, payer.key, args.mint_nonce);
+    if args.pda_mint {
+        if mint_pda != *mint_account.key {
+            return Err(TokenError::WRONG_mint_account.into());
+        }
+        // Lamports alone do not make a mint, the address may have been
+        // pre-funded to block the launch.
+        if *mint_account.owner != *system_program.key || !mint_account.data_is_empty() {
+            return Err(TokenError::MintAlreadyExists.into());
+        }
+    }
+    create_pda_account(
+        payer,
+        mint_account,
+        Mint::LEN,
+        token_program.key,
+        system_program,
         &[
-            mint_account.clone(),
-            payer.clone(),
-            system_program.clone(),
-            token_program.clone(),
+            b"mint",
+            payer.key.as_ref(),
+            &args.mint_nonce.to_le_bytes(),
+            &[mint_bump],
         ],
     )?;
 
@@ -77,7 +261,11 @@ pub fn create_token(
             token_program.key,
             mint_account.key,
             mint_authority.key,
-            Some(mint_authority.key),
+            if args.no_freeze_authority {
+                None
+            } else {
+                Some(mint_authority.key)
+            },
             7, // 7 Decimals for the default SPL Token standard
         )?,
         &[
@@ -110,7 +298,13 @@ pub fn create_token(
     msg!("Token mint created successfully.");
 
     //State struct
-    let (state_pda, blog_bump) = Pubkey::find_program_address(&[b"state".as_ref()], program_id);
+    let (state_pda, blog_bump) = match &registration {
+        Some(_) => Pubkey::find_program_address(&[b"state", mint_account.key.as_ref()], program_id),
+        None => Pubkey::find_program_address(&[b"state".as_ref()], program_id),
+    };
+    if registration.is_some() && state_pda != *state_account.key {
+        return Err(TokenError::WrongStateAccount.into());
+    }
     let rent = Rent::get()?;
     let rent_lamports = rent.minimum_balance(State::LEN);
 
@@ -122,10 +316,16 @@ pub fn create_token(
         program_id,
     );
     msg!("Creating State account!");
+    let launch_seeds: &[&[u8]] = &[b"state", mint_account.key.as_ref(), &[blog_bump]];
+    let single_seeds: &[&[u8]] = &[b"state".as_ref(), &[blog_bump]];
     invoke_signed(
         create_blog_pda_ix,
         &[payer.clone(), state_account.clone(), system_program.clone()],
-        &[&[b"state".as_ref(), &[blog_bump]]],
+        &[if registration.is_some() {
+            launch_seeds
+        } else {
+            single_seeds
+        }],
     )?;
 
     let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
@@ -134,32 +334,135 @@ pub fn create_token(
     account_state.total = 0;
     account_state.claimed = 0;
     account_state.claim_authority = args.claime_authority;
+    account_state.curve_supply = 0;
+    account_state.curve = args.curve;
+    account_state.status = LaunchStatus::Active;
+    account_state.graduation_threshold = args.graduation_threshold;
+    account_state.pool_token_allocation = args.pool_token_allocation;
+    account_state.soft_cap = args.soft_cap;
+    account_state.hard_cap = args.hard_cap;
+    account_state.deadline = args.deadline;
+    account_state.trading_starts_at = args.trading_starts_at;
+    if args.launch_fee_rate > 0 && args.trading_starts_at == 0 {
+        // The launch fee decays from the moment trading opens.
+        account_state.trading_starts_at = Clock::get()?.unix_timestamp;
+    }
+    account_state.sells_enabled_at = args.sells_enabled_at;
+    account_state.allowlist_root = args.allowlist_root;
+    account_state.allowlist_starts_at = args.allowlist_starts_at;
+    account_state.wallet_token_cap = args.wallet_token_cap;
+    account_state.wallet_stable_cap = args.wallet_stable_cap;
+    account_state.max_price_impact = args.max_price_impact;
+    account_state.vesting_amount = args.vesting_amount;
+    account_state.curve_supply = args.vesting_amount;
+    if account_state.curve_supply > 0 {
+        (account_state.reserve, _) =
+            args.curve
+                .stable_for_exact_tokens(None, 0, account_state.curve_supply, 0)?;
+    }
+    account_state.buyback_bps = args.buyback_bps;
+    account_state.buyback_interval = args.buyback_interval;
+    account_state.buyback_cap = args.buyback_cap;
+    account_state.staking_fee_bps = args.staking_fee_bps;
+    // A schedule curve is priced once `SetSchedule` stores its rounds.
+    account_state.update_price(
+        Clock::get()?.unix_timestamp,
+        args.curve
+            .spot_price(None, account_state.curve_supply)
+            .unwrap_or(0),
+    );
+    account_state.launch_fee_rate = args.launch_fee_rate;
+    account_state.fee_decay_period = args.fee_decay_period;
+    account_state.referral_share = args.referral_share;
+    account_state.creator = args.creator;
+    account_state.creator_fee_bps = args.creator_fee_bps;
+    if let Some(registration) = registration {
+        account_state.config = registration.config;
+        account_state.launch_id = registration.launch_id;
+        account_state.protocol_fee_bps = registration.protocol_fee_bps;
+    }
+    account_state.registry_index = register_launch(
+        program_id,
+        registry_account,
+        registry_page_account,
+        payer,
+        system_program,
+        LaunchRecord {
+            state: *state_account.key,
+            mint: *mint_account.key,
+            creator,
+            trade_token: *trade_token_account.key,
+            created_slot: Clock::get()?.slot,
+            status: account_state.status,
+        },
+    )?;
     account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
 
-    //4.1. Create ATA pda if needed
-    let (pda_trade_token_authority_address, bump_seed_3) =
-        Pubkey::find_program_address(&[b"ata_trade_token_authority"], &program_id);
+    //4.1. Create the launch's vault. Its address is new with the state, so
+    // it is created even when someone funded it first.
+    let (pda_trade_token_authority_address, _) = vault_authority(program_id, state_account.key);
 
-    if pda_trade_token_ata_account.lamports() == 0 {
-        msg!("Creating associated token account...");
-        invoke_signed(
-            &associated_token_account_instruction::create_associated_token_account(
-                payer.key,
-                &pda_trade_token_authority_address,
-                trade_token_account.key,
+    msg!("Creating associated token account...");
+    invoke(
+        &associated_token_account_instruction::create_associated_token_account(
+            payer.key,
+            &pda_trade_token_authority_address,
+            trade_token_account.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            pda_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+            trade_token_account.clone(),
+            system_program.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    if args.vesting_amount > 0 {
+        let vesting_account = next_account_info(accounts_iter)?;
+        let vesting_escrow_account = next_account_info(accounts_iter)?;
+        let payer_trade_token_ata_account = next_account_info(accounts_iter)?;
+        let now = Clock::get()?.unix_timestamp;
+        create_vesting(
+            program_id,
+            &Vesting {
+                state: *state_account.key,
+                beneficiary: args.vesting_beneficiary,
+                amount: args.vesting_amount,
+                claimed: 0,
+                start: now,
+                cliff_end: now + args.vesting_cliff,
+                end: now + args.vesting_duration,
+            },
+            state_account,
+            mint_account,
+            mint_authority,
+            payer,
+            vesting_account,
+            vesting_escrow_account,
+            system_program,
+            token_program,
+            _associated_token_program,
+        )?;
+        // Paid as if bought first, so the reserve backs the pre-allocation.
+        invoke(
+            &token_instruction::transfer(
                 token_program.key,
-            ),
+                payer_trade_token_ata_account.key,
+                pda_trade_token_ata_account.key,
+                payer.key,
+                &[],
+                account_state.reserve,
+            )?,
             &[
-                payer.clone(),
+                payer_trade_token_ata_account.clone(),
                 pda_trade_token_ata_account.clone(),
-                pda_trade_token_authority.clone(),
-                trade_token_account.clone(),
-                system_program.clone(),
-                token_program.clone(),
+                payer.clone(),
             ],
-            &[&[b"ata_trade_token_authority", &[bump_seed_3]]],
         )?;
-    };
+    }
 
     Ok(())
 }
@@ -171,7 +474,222 @@ pub struct State {
     pub total: u64,
     pub claimed: u64,
     pub claim_authority: Pubkey,
+    /// Supply sold through the curve. Only changed by the mint and burn
+    /// instructions, so burns made directly through the token program do not
+    /// move the price.
+    pub curve_supply: u64,
+    pub curve: CurveConfig,
+    pub status: LaunchStatus,
+    pub graduation_threshold: u64,
+    pub pool_token_allocation: u64,
+    /// Stable backing the curve, fees excluded.
+    pub reserve: u64,
+    pub pool_stable: u64,
+    pub pool_tokens: u64,
+    pub soft_cap: u64,
+    pub hard_cap: u64,
+    pub deadline: i64,
+    /// Stable paid into the presale, fees included.
+    pub raised: u64,
+    pub trading_starts_at: i64,
+    pub sells_enabled_at: i64,
+    pub allowlist_root: [u8; 32],
+    pub allowlist_starts_at: i64,
+    pub wallet_token_cap: u64,
+    pub wallet_stable_cap: u64,
+    pub max_price_impact: u64,
+    pub launch_fee_rate: u64,
+    pub fee_decay_period: i64,
+    /// Stable paid for buys, fees included.
+    pub buy_volume: u64,
+    /// Stable paid out for sells, fees excluded.
+    pub sell_volume: u64,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    /// Highest spot price reached by a buy and the curve round it was reached
+    /// in, counted from 1. The round is 0 for curves without rounds and for
+    /// pool prices.
+    pub ath_price: u64,
+    pub ath_round: u64,
+    pub last_trade_slot: u64,
+    /// Buyers counted on the first buy tracked by their `UserStats`. Buyers
+    /// that never pass their stats are not counted.
+    pub unique_buyers: u64,
+    /// Sum of `last_price` times the seconds it was the spot price.
+    pub price_cumulative: u128,
+    /// Spot price after the last trade.
+    pub last_price: u64,
+    pub price_updated_at: i64,
+    /// Set by `InitOracle`; trades then pass the observation ring.
+    pub oracle_enabled: bool,
+    /// Set by `InitCandles`; trades then pass the candles account.
+    pub candles_enabled: bool,
+    /// See `CreateTokenArgs::referral_share`.
+    pub referral_share: u64,
+    pub creator: Pubkey,
+    pub creator_fee_bps: u64,
+    /// Royalties accrued to `creator`, kept out of `total`.
+    pub creator_fees: u64,
+    pub creator_claimed: u64,
+    /// Launchpad config of a `CreateLaunch` launch, default otherwise.
+    pub config: Pubkey,
+    pub launch_id: u64,
+    /// Part of `total` owed to the protocol, in basis points.
+    pub protocol_fee_bps: u64,
+    pub protocol_claimed: u64,
+    /// Position of the launch in the registry.
+    pub registry_index: u64,
+    /// Set by the first `FreezeHolder`; trades then pass the trader's
+    /// denylist entry.
+    pub denylist_enabled: bool,
+    /// Pre-allocation minted into the vesting escrow at creation.
+    pub vesting_amount: u64,
+    /// See `CreateTokenArgs::buyback_bps`.
+    pub buyback_bps: u64,
+    pub buyback_interval: i64,
+    pub buyback_cap: u64,
+    /// Fees spent and curve supply bought by `Buyback`. The bought supply
+    /// is counted in `curve_supply` but was never minted.
+    pub buyback_spent: u64,
+    pub buyback_burned: u64,
+    pub last_buyback_at: i64,
+    /// See `CreateTokenArgs::staking_fee_bps`.
+    pub staking_fee_bps: u64,
+    pub total_staked: u64,
+    /// Stable earned per staked token, scaled by `REWARD_PRECISION`.
+    pub reward_per_token: u128,
+    /// Fees shared with stakers, kept out of `total`.
+    pub staking_rewards: u64,
+    pub staking_claimed: u64,
 }
 impl State {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 32;
+    pub const LEN: usize = 32
+        + 32
+        + 8
+        + 8
+        + 32
+        + 8
+        + CurveConfig::LEN
+        + 1
+        + 8 * 5
+        + 8 * 4
+        + 8 * 2
+        + 32
+        + 8
+        + 8 * 3
+        + 8 * 2
+        + 8 * 8
+        + 16
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8
+        + 32
+        + 8 * 3
+        + 32
+        + 8 * 3
+        + 8
+        + 1
+        + 8
+        + 8 * 6
+        + 8 * 2
+        + 16
+        + 8 * 2;
+
+    /// Mint supply expected when no tokens were burned outside the program.
+    pub fn minted_supply(&self) -> u64 {
+        let minted = self.curve_supply - self.buyback_burned;
+        if self.status == LaunchStatus::Graduated {
+            minted + self.pool_token_allocation
+        } else {
+            minted
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LaunchStatus {
+    #[default]
+    Active,
+    /// The curve reached its max supply. Buys are closed, sells stay open.
+    SaleCompleted,
+    /// The curve is frozen and trades go through the built-in pool.
+    Graduated,
+    /// The presale reached its soft cap. Trading is open.
+    Succeeded,
+    /// The presale missed its soft cap. Buyers can `Refund`.
+    Failed,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{processor::SplMinterIntstruction, test_utils::*};
+    use solana_program::instruction::AccountMeta;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn pda_mints_are_derived_from_payer_and_nonce() {
+        let mut launch = TestLaunch::new().await;
+        let creator = Pubkey::new_unique();
+        let (mint, _) = mint_address(&launch.program_id, &launch.payer(), 7);
+        let create = |launch: &mut TestLaunch, launch_mint| {
+            let mut args = create_args(stepped_linear());
+            args.claime_authority = launch.payer();
+            args.creator = creator;
+            args.pda_mint = true;
+            args.mint_nonce = 7;
+            let state = Keypair::new();
+            launch.mint = launch_mint;
+            launch.state = state.pubkey();
+            (args, state)
+        };
+
+        // The signing payer seeds the mint, so a launch can not claim the
+        // address of another creator.
+        let (creator_mint, _) = mint_address(&launch.program_id, &creator, 7);
+        let (args, state) = create(&mut launch, creator_mint);
+        let mut accounts = launch.create_accounts().await;
+        accounts[0] = AccountMeta::new(launch.mint, false);
+        let result = launch
+            .process_with_signers(SplMinterIntstruction::Create(args), accounts, &[&state])
+            .await;
+        assert_token_error(result, TokenError::WRONG_mint_account);
+
+        // Lamports sent to the mint address beforehand do not block it.
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        let payer = launch.payer();
+        launch
+            .send(
+                &[system_instruction::transfer(&payer, &mint, lamports)],
+                &[],
+            )
+            .await
+            .unwrap();
+        let (args, state) = create(&mut launch, mint);
+        let mut accounts = launch.create_accounts().await;
+        accounts[0] = AccountMeta::new(mint, false);
+        launch
+            .process_with_signers(SplMinterIntstruction::Create(args), accounts, &[&state])
+            .await
+            .unwrap();
+        assert_eq!(launch.account(mint).await.owner, spl_token::id());
+        assert_eq!(launch.mint_supply().await, 0);
+
+        // The same nonce can not launch twice.
+        let (args, state) = create(&mut launch, mint);
+        let mut accounts = launch.create_accounts().await;
+        accounts[0] = AccountMeta::new(mint, false);
+        let result = launch
+            .process_with_signers(SplMinterIntstruction::Create(args), accounts, &[&state])
+            .await;
+        assert_token_error(result, TokenError::MintAlreadyExists);
+    }
 }
@@ -0,0 +1,248 @@
+use super::{
+    claim::ClaimArgs,
+    create::{vault_authority, State},
+    trade::{mul_div_u64, PERCENT_DENOMINATOR},
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Highest royalty a launch can charge, 10%.
+pub const MAX_CREATOR_FEE_BPS: u64 = 1_000;
+
+pub fn validate_creator_fee(creator: &Pubkey, creator_fee_bps: u64) -> ProgramResult {
+    if creator_fee_bps > MAX_CREATOR_FEE_BPS
+        || (creator_fee_bps > 0 && *creator == Pubkey::default())
+    {
+        return Err(TokenError::InvalidCreatorFee.into());
+    }
+    Ok(())
+}
+
+impl State {
+    /// Royalty added to the buy fee, in the units of `FEE_PERCENT_POINT`.
+    /// Presale buys pay none, refunds hand their fees back.
+    pub fn creator_fee_rate(&self) -> u64 {
+        if self.in_presale() {
+            return 0;
+        }
+        self.creator_fee_bps * PERCENT_DENOMINATOR / 100
+    }
+
+    /// Creator part of a buy `fee` charged at `fee_rate`.
+    pub fn creator_buy_fee(&self, fee: u64, fee_rate: u64) -> u64 {
+        mul_div_u64(fee, self.creator_fee_rate(), fee_rate).unwrap_or(0)
+    }
+
+    /// Splits the royalty off the stable a sell pays out.
+    pub fn take_creator_fee(&self, out_amount: u64) -> (u64, u64) {
+        let fee = mul_div_u64(out_amount, self.creator_fee_bps, BPS_DENOMINATOR).unwrap_or(0);
+        (out_amount - fee, fee)
+    }
+}
+
+/// Pays accrued royalties to the creator. Same accounts as `Claim`, signed by
+/// the creator instead of the claim authority.
+pub fn claim_creator_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ClaimArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let creator = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let creator_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let trade_token = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !creator.is_signer || *creator.key != account_state.creator {
+        return Err(TokenError::Unauthorized.into());
+    }
+    account_state.settle_presale(Clock::get()?.unix_timestamp);
+    account_state.check_presale_settled()?;
+    if args.amount == 0 || args.amount > account_state.creator_fees - account_state.creator_claimed
+    {
+        return Err(TokenError::NothingToClaim.into());
+    }
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+
+    account_state.creator_claimed += args.amount;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    if creator_trade_token_ata_account.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                creator.key,
+                creator.key,
+                trade_token.key,
+                token_program.key,
+            ),
+            &[
+                trade_token.clone(),
+                creator_trade_token_ata_account.clone(),
+                creator.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            creator_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            args.amount,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            creator_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!(
+        "Creator fees claimed {} {}, accrued: {}",
+        creator.key,
+        args.amount,
+        account_state.creator_fees
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs},
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn creator_earns_royalty_on_buys_and_sells() {
+        let creator = Keypair::new();
+        assert!(validate_creator_fee(&Pubkey::default(), 100).is_err());
+        assert!(validate_creator_fee(&creator.pubkey(), MAX_CREATOR_FEE_BPS + 1).is_err());
+        let mut args = create_args(stepped_linear());
+        args.creator = creator.pubkey();
+        args.creator_fee_bps = 100;
+        let mut launch = TestLaunch::start(args).await;
+        let payer = launch.payer();
+        launch
+            .send(
+                &[system_instruction::transfer(
+                    &payer,
+                    &creator.pubkey(),
+                    1_000_000_000,
+                )],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 1_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        // 1% on top of the 5% fee, so a sixth of the fee.
+        let bought = launch.state().await;
+        let fee = 1_000_000 - bought.reserve;
+        assert_eq!(bought.creator_fees, fee / 6);
+        assert_eq!(bought.total, fee - fee / 6);
+
+        let balance = launch.token_balance(launch.payer_stable()).await;
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+        launch
+            .process(
+                SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                    _inAmount: tokens,
+                }),
+                launch.burn_accounts(),
+            )
+            .await
+            .unwrap();
+        let paid = launch.token_balance(launch.payer_stable()).await - balance;
+        let sold = launch.state().await;
+        let royalty = sold.creator_fees - bought.creator_fees;
+        assert_eq!(royalty, (paid + royalty) / 100);
+
+        let creator_stable = get_associated_token_address(&creator.pubkey(), &launch.stable_mint);
+        let claim_accounts = vec![
+            AccountMeta::new(creator.pubkey(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(launch.vault(), false),
+            AccountMeta::new(creator_stable, false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new_readonly(launch.stable_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let mut payer_accounts = claim_accounts.clone();
+        payer_accounts[0] = AccountMeta::new(payer, true);
+        let claim = |amount| SplMinterIntstruction::ClaimCreatorFees(ClaimArgs { amount });
+        let result = launch
+            .process(claim(sold.creator_fees), payer_accounts)
+            .await;
+        assert_token_error(result, TokenError::Unauthorized);
+        launch
+            .process_with_signers(
+                claim(sold.creator_fees),
+                claim_accounts.clone(),
+                &[&creator],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(creator_stable).await,
+            sold.creator_fees
+        );
+        let result = launch
+            .process_with_signers(claim(1), claim_accounts.clone(), &[&creator])
+            .await;
+        assert_token_error(result, TokenError::NothingToClaim);
+    }
+}
@@ -0,0 +1,417 @@
+use super::create::{create_pda_account, State};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke_signed,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::{
+        instruction::{self as token_instruction, AuthorityType},
+        state::{Account as TokenAccount, AccountState},
+    },
+};
+
+/// Denylist entry of a holder, stored at `[b"denylist", state, holder]`.
+/// Kept after `ThawHolder` with `denied` cleared.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DenylistEntry {
+    pub state: Pubkey,
+    pub holder: Pubkey,
+    pub denied: bool,
+    /// Time of the last `FreezeHolder` or `ThawHolder`.
+    pub updated_at: i64,
+}
+
+impl DenylistEntry {
+    pub const LEN: usize = 32 + 32 + 1 + 8;
+
+    pub fn address(program_id: &Pubkey, state: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"denylist", state.as_ref(), holder.as_ref()], program_id)
+    }
+
+    /// Reads the entry of `holder`, default when it was never denied.
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        holder: &Pubkey,
+        entry_account: &AccountInfo,
+    ) -> Result<DenylistEntry, ProgramError> {
+        let (address, _) = DenylistEntry::address(program_id, state_account.key, holder);
+        if address != *entry_account.key {
+            return Err(TokenError::WrongDenylistAccount.into());
+        }
+        if entry_account.owner != program_id {
+            return Ok(DenylistEntry::default());
+        }
+        DenylistEntry::try_from_slice(&entry_account.data.borrow())
+            .map_err(|_| TokenError::WrongDenylistAccount.into())
+    }
+}
+
+/// Takes the trader's denylist entry once the launch has one and fails if the
+/// trader is denied.
+pub fn check_denylist<'a, 'b>(
+    program_id: &Pubkey,
+    state_account: &AccountInfo<'a>,
+    account_state: &State,
+    trader: &AccountInfo<'a>,
+    accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+) -> ProgramResult {
+    if !account_state.denylist_enabled {
+        return Ok(());
+    }
+    let entry_account = next_account_info(accounts_iter)?;
+    if DenylistEntry::load(program_id, state_account, trader.key, entry_account)?.denied {
+        return Err(TokenError::HolderDenied.into());
+    }
+    Ok(())
+}
+
+/// Checks the signer is the claim authority of the launch and the token
+/// account is the holder's for its mint.
+fn check_holder_admin(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    state_account: &AccountInfo,
+    holder: &AccountInfo,
+    holder_token_account: &AccountInfo,
+    mint_account: &AccountInfo,
+) -> Result<State, ProgramError> {
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !admin.is_signer || *admin.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+    if get_associated_token_address(holder.key, mint_account.key) != *holder_token_account.key {
+        return Err(TokenError::WrongHolderAccount.into());
+    }
+    Ok(account_state)
+}
+
+/// Token account state of the holder, `None` while it does not exist.
+fn holder_token_state(
+    holder_token_account: &AccountInfo,
+) -> Result<Option<AccountState>, ProgramError> {
+    if holder_token_account.lamports() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(
+        TokenAccount::unpack(&holder_token_account.data.borrow())?.state,
+    ))
+}
+
+/// Denies a holder: freezes their token account, if they have one, and stops
+/// them from trading. Accounts: claim authority (pays the entry), state,
+/// holder, holder token account, denylist entry, mint, mint authority, token
+/// program, system program.
+pub fn freeze_holder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let holder = next_account_info(accounts_iter)?;
+    let holder_token_account = next_account_info(accounts_iter)?;
+    let entry_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let mut account_state = check_holder_admin(
+        program_id,
+        admin,
+        state_account,
+        holder,
+        holder_token_account,
+        mint_account,
+    )?;
+
+    let mut entry = DenylistEntry::load(program_id, state_account, holder.key, entry_account)?;
+    if entry.denied {
+        return Err(TokenError::HolderDenied.into());
+    }
+    if entry_account.owner != program_id {
+        let (_, bump) = DenylistEntry::address(program_id, state_account.key, holder.key);
+        create_pda_account(
+            admin,
+            entry_account,
+            DenylistEntry::LEN,
+            program_id,
+            system_program,
+            &[
+                b"denylist",
+                state_account.key.as_ref(),
+                holder.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        entry.state = *state_account.key;
+        entry.holder = *holder.key;
+    }
+    entry.denied = true;
+    entry.updated_at = Clock::get()?.unix_timestamp;
+    entry.serialize(&mut &mut entry_account.data.borrow_mut()[..])?;
+
+    if !account_state.denylist_enabled {
+        account_state.denylist_enabled = true;
+        account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+    }
+
+    if holder_token_state(holder_token_account)? == Some(AccountState::Initialized) {
+        let (mint_authority_address, bump) =
+            Pubkey::find_program_address(&[b"mint_authority"], program_id);
+        invoke_signed(
+            &token_instruction::freeze_account(
+                token_program.key,
+                holder_token_account.key,
+                mint_account.key,
+                &mint_authority_address,
+                &[],
+            )?,
+            &[
+                holder_token_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+            ],
+            &[&[b"mint_authority", &[bump]]],
+        )?;
+    }
+
+    msg!("HolderFrozen {} {}", state_account.key, holder.key);
+    Ok(())
+}
+
+/// Lifts a `FreezeHolder`. Same accounts, without the system program.
+pub fn thaw_holder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let holder = next_account_info(accounts_iter)?;
+    let holder_token_account = next_account_info(accounts_iter)?;
+    let entry_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    check_holder_admin(
+        program_id,
+        admin,
+        state_account,
+        holder,
+        holder_token_account,
+        mint_account,
+    )?;
+
+    let mut entry = DenylistEntry::load(program_id, state_account, holder.key, entry_account)?;
+    if !entry.denied {
+        return Err(TokenError::HolderNotDenied.into());
+    }
+    entry.denied = false;
+    entry.updated_at = Clock::get()?.unix_timestamp;
+    entry.serialize(&mut &mut entry_account.data.borrow_mut()[..])?;
+
+    if holder_token_state(holder_token_account)? == Some(AccountState::Frozen) {
+        let (mint_authority_address, bump) =
+            Pubkey::find_program_address(&[b"mint_authority"], program_id);
+        invoke_signed(
+            &token_instruction::thaw_account(
+                token_program.key,
+                holder_token_account.key,
+                mint_account.key,
+                &mint_authority_address,
+                &[],
+            )?,
+            &[
+                holder_token_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+            ],
+            &[&[b"mint_authority", &[bump]]],
+        )?;
+    }
+
+    msg!("HolderThawed {} {}", state_account.key, holder.key);
+    Ok(())
+}
+
+/// Drops the freeze authority of the mint for good. Accounts: claim
+/// authority, state, mint, mint authority, token program.
+pub fn revoke_freeze_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !admin.is_signer || *admin.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+
+    let (mint_authority_address, bump) =
+        Pubkey::find_program_address(&[b"mint_authority"], program_id);
+    invoke_signed(
+        &token_instruction::set_authority(
+            token_program.key,
+            mint_account.key,
+            None,
+            AuthorityType::FreezeAccount,
+            &mint_authority_address,
+            &[],
+        )?,
+        &[mint_account.clone(), mint_authority.clone()],
+        &[&[b"mint_authority", &[bump]]],
+    )?;
+
+    msg!("Freeze authority revoked: {}", mint_account.key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs};
+    use crate::{processor::SplMinterIntstruction, test_utils::*};
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+    use spl_token::state::Mint;
+
+    #[tokio::test]
+    async fn freeze_authority_can_be_skipped_or_revoked() {
+        let mut args = create_args(stepped_linear());
+        args.no_freeze_authority = true;
+        let mut launch = TestLaunch::start(args).await;
+        let mint = Mint::unpack(&launch.account(launch.mint).await.data).unwrap();
+        assert!(mint.freeze_authority.is_none());
+
+        launch.create(create_args(stepped_linear())).await;
+        let mint = Mint::unpack(&launch.account(launch.mint).await.data).unwrap();
+        assert!(mint.freeze_authority.is_some());
+        let (mint_authority, _) =
+            Pubkey::find_program_address(&[b"mint_authority"], &launch.program_id);
+        let accounts = vec![
+            AccountMeta::new_readonly(launch.payer(), true),
+            AccountMeta::new_readonly(launch.state, false),
+            AccountMeta::new(launch.mint, false),
+            AccountMeta::new_readonly(mint_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        let mut wrong_mint = accounts.clone();
+        wrong_mint[2] = AccountMeta::new(launch.stable_mint, false);
+        let result = launch
+            .process(SplMinterIntstruction::RevokeFreezeAuthority, wrong_mint)
+            .await;
+        assert_token_error(result, TokenError::WRONG_mint_account);
+
+        launch
+            .process(SplMinterIntstruction::RevokeFreezeAuthority, accounts)
+            .await
+            .unwrap();
+        let mint = Mint::unpack(&launch.account(launch.mint).await.data).unwrap();
+        assert!(mint.freeze_authority.is_none());
+    }
+
+    #[tokio::test]
+    async fn denied_holders_are_frozen_and_can_not_trade() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+        launch
+            .process(buy(1_000_000), launch.mint_accounts())
+            .await
+            .unwrap();
+
+        let holder = launch.payer();
+        let (entry, _) = DenylistEntry::address(&launch.program_id, &launch.state, &holder);
+        let (mint_authority, _) =
+            Pubkey::find_program_address(&[b"mint_authority"], &launch.program_id);
+        let accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new_readonly(holder, false),
+            AccountMeta::new(launch.payer_tokens(), false),
+            AccountMeta::new(entry, false),
+            AccountMeta::new_readonly(launch.mint, false),
+            AccountMeta::new_readonly(mint_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        // Lamports sent to the entry beforehand must not stop the freeze.
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        launch
+            .send(
+                &[system_instruction::transfer(&holder, &entry, lamports)],
+                &[],
+            )
+            .await
+            .unwrap();
+        launch
+            .process(SplMinterIntstruction::FreezeHolder, accounts.clone())
+            .await
+            .unwrap();
+        let token_account =
+            TokenAccount::unpack(&launch.account(launch.payer_tokens()).await.data).unwrap();
+        assert_eq!(token_account.state, AccountState::Frozen);
+        assert!(launch.state().await.denylist_enabled);
+        let result = launch
+            .process(SplMinterIntstruction::FreezeHolder, accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::HolderDenied);
+
+        let mut mint_accounts = launch.mint_accounts();
+        mint_accounts.push(AccountMeta::new_readonly(entry, false));
+        let result = launch.process(buy(1_000_000), mint_accounts.clone()).await;
+        assert_token_error(result, TokenError::HolderDenied);
+        let mut burn_accounts = launch.burn_accounts();
+        burn_accounts.push(AccountMeta::new_readonly(entry, false));
+        let sell = SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+            _inAmount: 1,
+        });
+        let result = launch.process(sell, burn_accounts).await;
+        assert_token_error(result, TokenError::HolderDenied);
+
+        launch
+            .process(SplMinterIntstruction::ThawHolder, accounts[..8].to_vec())
+            .await
+            .unwrap();
+        let token_account =
+            TokenAccount::unpack(&launch.account(launch.payer_tokens()).await.data).unwrap();
+        assert_eq!(token_account.state, AccountState::Initialized);
+        launch.process(buy(1_000_000), mint_accounts).await.unwrap();
+        let result = launch
+            .process(SplMinterIntstruction::ThawHolder, accounts[..8].to_vec())
+            .await;
+        assert_token_error(result, TokenError::HolderNotDenied);
+    }
+}
@@ -0,0 +1,671 @@
+use super::{
+    candles::{next_candles, save_candles},
+    create::{vault_authority, LaunchStatus, State},
+    freeze::check_denylist,
+    oracle::{next_oracle, save_oracle},
+    referral::{credit_referral, next_trade_extras, save_referral},
+    stats::save_user_stats,
+    wallet::WalletRecord,
+};
+use crate::{
+    curve::{add_buy_fee, take_buy_fee, take_sell_fee, TOKEN_UNIT},
+    error::TokenError,
+    instructions::trade::{mul_div_u64, FEE_PERCENT_POINT},
+};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+/// Freezes the curve once `graduation_threshold` is sold and seeds the
+/// built-in pool with the curve reserve and `pool_token_allocation` tokens.
+/// Anyone can crank it.
+pub fn graduate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let pool_token_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+    account_state.settle_presale(Clock::get()?.unix_timestamp);
+    account_state.check_presale_settled()?;
+    if account_state.status == LaunchStatus::Graduated
+        || account_state.graduation_threshold == 0
+        || account_state.curve_supply < account_state.graduation_threshold
+    {
+        return Err(TokenError::NotGraduatable.into());
+    }
+
+    let (pda_trade_token_authority_address, _) = vault_authority(program_id, state_account.key);
+    if get_associated_token_address(&pda_trade_token_authority_address, mint_account.key)
+        != *pool_token_account.key
+    {
+        return Err(TokenError::WrongPoolAccount.into());
+    }
+
+    if pool_token_account.lamports() == 0 {
+        msg!("Creating pool token account...");
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                payer.key,
+                &pda_trade_token_authority_address,
+                mint_account.key,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                pool_token_account.clone(),
+                pda_trade_token_authority.clone(),
+                mint_account.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+
+    let (_, mint_authority_bump) = Pubkey::find_program_address(&[b"mint_authority"], program_id);
+    invoke_signed(
+        &token_instruction::mint_to(
+            token_program.key,
+            mint_account.key,
+            pool_token_account.key,
+            mint_authority.key,
+            &[mint_authority.key],
+            account_state.pool_token_allocation,
+        )?,
+        &[
+            mint_account.clone(),
+            mint_authority.clone(),
+            pool_token_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[b"mint_authority", &[mint_authority_bump]]],
+    )?;
+
+    account_state.pool_stable = account_state.reserve;
+    account_state.pool_tokens = account_state.pool_token_allocation;
+    account_state.reserve = 0;
+    account_state.status = LaunchStatus::Graduated;
+    if let Some(price) = account_state.pool_price() {
+        account_state.update_price(Clock::get()?.unix_timestamp, price);
+    }
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Graduated pool_stable: {}, pool_tokens: {}",
+        account_state.pool_stable,
+        account_state.pool_tokens
+    );
+    Ok(())
+}
+
+impl State {
+    /// Spot price of the pool, in stable units per whole token.
+    pub fn pool_price(&self) -> Option<u64> {
+        mul_div_u64(self.pool_stable, TOKEN_UNIT, self.pool_tokens)
+    }
+
+    /// Pool buy fee, creator royalty included.
+    pub fn pool_fee_rate(&self) -> u64 {
+        FEE_PERCENT_POINT + self.creator_fee_rate()
+    }
+}
+
+/// Tokens out of the pool for `in_amount` stable (fee included) and the fee.
+pub fn pool_buy(account_state: &State, in_amount: u64) -> Result<(u64, u64), ProgramError> {
+    let (clean, fee) = take_buy_fee(in_amount, account_state.pool_fee_rate());
+    let out = constant_product_out(account_state.pool_stable, account_state.pool_tokens, clean)?;
+    Ok((out, fee))
+}
+
+/// Stable (fee included) the pool takes for exactly `tokens` and the fee.
+pub fn pool_stable_for_exact_tokens(
+    account_state: &State,
+    tokens: u64,
+) -> Result<(u64, u64), ProgramError> {
+    let tokens_left = account_state
+        .pool_tokens
+        .checked_sub(tokens)
+        .filter(|tokens_left| *tokens_left > 0)
+        .ok_or(TokenError::MathOverflow)?;
+    let clean = mul_div_u64(account_state.pool_stable, tokens, tokens_left)
+        .and_then(|clean| clean.checked_add(1))
+        .ok_or(TokenError::MathOverflow)?;
+    add_buy_fee(clean, account_state.pool_fee_rate()).ok_or_else(|| TokenError::MathOverflow.into())
+}
+
+/// Stable out of the pool for `in_amount` tokens (fee excluded) and the fee.
+pub fn pool_sell(account_state: &State, in_amount: u64) -> Result<(u64, u64), ProgramError> {
+    let gross = constant_product_out(
+        account_state.pool_tokens,
+        account_state.pool_stable,
+        in_amount,
+    )?;
+    Ok(take_sell_fee(gross))
+}
+
+fn constant_product_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+) -> Result<u64, ProgramError> {
+    let new_reserve_in = reserve_in
+        .checked_add(amount_in)
+        .ok_or(TokenError::MathOverflow)?;
+    mul_div_u64(reserve_out, amount_in, new_reserve_in)
+        .ok_or_else(|| TokenError::MathOverflow.into())
+}
+
+/// `mintTokensForExactStable` after graduation. Same accounts, followed by
+/// the pool token account, the trade token authority, the buyer's wallet
+/// record when the launch caps wallets, the oracle, the candles and the
+/// buyer's denylist entry once initialized and optionally the buyer's
+/// `UserStats` and a referrer with its `Referral`. Wallet caps and the max
+/// price impact apply as on the curve.
+pub fn swap_stable_for_tokens(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    in_amount: u64,
+    out_not_less: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let mint_account = next_account_info(accounts_iter)?;
+    let _mint_authority = next_account_info(accounts_iter)?;
+    let associated_token_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let payer_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+    let pool_token_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let mut in_amount = in_amount;
+    let mut wallet_record = None;
+    let mut wallet_tokens = u64::MAX;
+    if account_state.has_wallet_caps() {
+        let record_account = next_account_info(accounts_iter)?;
+        let record = WalletRecord::load_or_create(
+            program_id,
+            state_account,
+            payer,
+            record_account,
+            system_program,
+        )?;
+        let (tokens, stable) = account_state.wallet_room(&record)?;
+        wallet_tokens = tokens;
+        in_amount = in_amount.min(stable);
+        wallet_record = Some((record_account, record));
+    }
+    let oracle = next_oracle(program_id, state_account, &account_state, accounts_iter)?;
+    let candles = next_candles(program_id, state_account, &account_state, accounts_iter)?;
+    check_denylist(
+        program_id,
+        state_account,
+        &account_state,
+        payer,
+        accounts_iter,
+    )?;
+    let (mut stats, mut referral) = next_trade_extras(
+        program_id,
+        state_account,
+        payer,
+        accounts_iter,
+        Some(system_program),
+    )?;
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.mint_account,
+    ) != *pool_token_account.key
+    {
+        return Err(TokenError::WrongPoolAccount.into());
+    }
+
+    let (mut out_amount, mut fee) = pool_buy(&account_state, in_amount)?;
+    // Fill the buyer up to their wallet cap, charging only what they get.
+    if out_amount > wallet_tokens {
+        let (fill_amount, fill_fee) = pool_stable_for_exact_tokens(&account_state, wallet_tokens)?;
+        out_amount = wallet_tokens;
+        in_amount = fill_amount.min(in_amount);
+        fee = fill_fee.min(in_amount);
+    }
+    if out_amount == 0 {
+        return Err(TokenError::ZeroBuy.into());
+    }
+    if out_amount < out_not_less {
+        return Err(TokenError::Slippage_occur.into());
+    }
+    let price_before = account_state.pool_price().ok_or(TokenError::MathOverflow)?;
+
+    let creator_fee = account_state.creator_buy_fee(fee, account_state.pool_fee_rate());
+    let protocol_fee = fee - creator_fee;
+    let referral_fee = credit_referral(&mut referral, &account_state, protocol_fee);
+    let staker_fee = account_state.share_with_stakers(protocol_fee - referral_fee);
+    account_state.total += protocol_fee - referral_fee - staker_fee;
+    account_state.creator_fees += creator_fee;
+    account_state.pool_stable += in_amount - fee;
+    account_state.pool_tokens -= out_amount;
+    let first_buy = matches!(&stats, Some((_, stats)) if stats.tokens_bought == 0);
+    account_state.track_buy(in_amount, Clock::get()?.slot, first_buy);
+    let now = Clock::get()?.unix_timestamp;
+    let last_price = account_state.last_price;
+    if let Some(price) = account_state.pool_price() {
+        account_state.check_price_rise(price_before, price)?;
+        // Pool prices have no round.
+        account_state.track_price(price, 0);
+        account_state.update_price(now, price);
+    }
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+    if let Some((record_account, mut record)) = wallet_record {
+        record.bought_tokens += out_amount;
+        record.spent_stable += in_amount;
+        record.serialize(&mut &mut record_account.data.borrow_mut()[..])?;
+    }
+    if let Some((_, stats)) = &mut stats {
+        stats.record_buy(in_amount, out_amount);
+    }
+    save_user_stats(stats)?;
+    save_referral(referral)?;
+    save_oracle(oracle, &account_state, now)?;
+    save_candles(candles, &account_state, now, last_price, in_amount)?;
+
+    if associated_token_account.lamports() == 0 {
+        msg!("Creating associated token account...");
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                payer.key,
+                payer.key,
+                mint_account.key,
+                token_program.key,
+            ),
+            &[
+                mint_account.clone(),
+                associated_token_account.clone(),
+                payer.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+
+    invoke(
+        &token_instruction::transfer(
+            token_program.key,
+            payer_trade_token_ata_account.key,
+            pda_trade_token_ata_account.key,
+            payer.key,
+            &[payer.key],
+            in_amount,
+        )?,
+        &[
+            payer_trade_token_ata_account.clone(),
+            pda_trade_token_ata_account.clone(),
+            payer.clone(),
+        ],
+    )?;
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pool_token_account.key,
+            associated_token_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            out_amount,
+        )?,
+        &[
+            pool_token_account.clone(),
+            associated_token_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("Swap {} {} {} {}", payer.key, in_amount, out_amount, fee);
+    Ok(())
+}
+
+/// `burnExactTokensForStable` after graduation. Same accounts, followed by
+/// the pool token account, the oracle, the candles and the seller's denylist
+/// entry once initialized and optionally the system program with the seller's `UserStats` and a
+/// referrer with its `Referral`. Tokens go back to the pool instead of being
+/// burned.
+pub fn swap_tokens_for_stable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    in_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let _mint_account = next_account_info(accounts_iter)?;
+    let associated_token_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let payer_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let pool_token_account = next_account_info(accounts_iter)?;
+
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let oracle = next_oracle(program_id, state_account, &account_state, accounts_iter)?;
+    let candles = next_candles(program_id, state_account, &account_state, accounts_iter)?;
+    check_denylist(
+        program_id,
+        state_account,
+        &account_state,
+        payer,
+        accounts_iter,
+    )?;
+    let (mut stats, mut referral) =
+        next_trade_extras(program_id, state_account, payer, accounts_iter, None)?;
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.mint_account,
+    ) != *pool_token_account.key
+    {
+        return Err(TokenError::WrongPoolAccount.into());
+    }
+
+    let (sell_amount, fee) = pool_sell(&account_state, in_amount)?;
+    if sell_amount == 0 {
+        return Err(TokenError::ZeroBuy.into());
+    }
+    let (out_amount, creator_fee) = account_state.take_creator_fee(sell_amount);
+
+    let referral_fee = credit_referral(&mut referral, &account_state, fee);
+    let staker_fee = account_state.share_with_stakers(fee - referral_fee);
+    account_state.total += fee - referral_fee - staker_fee;
+    account_state.creator_fees += creator_fee;
+    account_state.pool_stable -= sell_amount + fee;
+    account_state.pool_tokens += in_amount;
+    account_state.track_sell(out_amount, Clock::get()?.slot);
+    let now = Clock::get()?.unix_timestamp;
+    let last_price = account_state.last_price;
+    if let Some(price) = account_state.pool_price() {
+        account_state.update_price(now, price);
+    }
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+    if let Some((_, stats)) = &mut stats {
+        stats.record_sell(in_amount, out_amount);
+    }
+    save_user_stats(stats)?;
+    save_referral(referral)?;
+    save_oracle(oracle, &account_state, now)?;
+    save_candles(candles, &account_state, now, last_price, out_amount)?;
+
+    invoke(
+        &token_instruction::transfer(
+            token_program.key,
+            associated_token_account.key,
+            pool_token_account.key,
+            payer.key,
+            &[payer.key],
+            in_amount,
+        )?,
+        &[
+            associated_token_account.clone(),
+            pool_token_account.clone(),
+            payer.clone(),
+        ],
+    )?;
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            payer_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            out_amount,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            payer_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("Swap {} {} {} {}", payer.key, in_amount, out_amount, fee);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::{
+            create::CreateTokenArgs,
+            trade::{
+                burnExactTokensForStableArgs, mintTokensForExactStableArgs, PERCENT_DENOMINATOR,
+                ROUND_VOLUME,
+            },
+        },
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_program};
+
+    #[tokio::test]
+    async fn graduated_launch_trades_through_pool() {
+        let mut args = create_args(stepped_linear());
+        args.graduation_threshold = ROUND_VOLUME;
+        args.pool_token_allocation = ROUND_VOLUME;
+        let mut launch = TestLaunch::start(args).await;
+        let pool = get_associated_token_address(&launch.vault_authority(), &launch.mint);
+        let graduate_accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(launch.mint, false),
+            AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"mint_authority"], &launch.program_id).0,
+                false,
+            ),
+            AccountMeta::new(pool, false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+
+        let result = launch
+            .process(SplMinterIntstruction::Graduate, graduate_accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::NotGraduatable);
+
+        // The buy crossing the threshold is filled up to it.
+        launch
+            .process(buy(100_000_000), launch.mint_accounts())
+            .await
+            .unwrap();
+        let state = launch.state().await;
+        assert_eq!(state.curve_supply, ROUND_VOLUME);
+        assert_eq!(state.status, LaunchStatus::SaleCompleted);
+        let reserve = state.reserve;
+        assert_eq!(
+            launch.token_balance(launch.vault()).await,
+            reserve + state.total
+        );
+
+        launch
+            .process(SplMinterIntstruction::Graduate, graduate_accounts.clone())
+            .await
+            .unwrap();
+        let state = launch.state().await;
+        assert_eq!(state.status, LaunchStatus::Graduated);
+        assert_eq!(state.pool_stable, reserve);
+        assert_eq!(state.pool_tokens, ROUND_VOLUME);
+        assert_eq!(launch.token_balance(pool).await, ROUND_VOLUME);
+        let result = launch
+            .process(SplMinterIntstruction::Graduate, graduate_accounts)
+            .await;
+        assert_token_error(result, TokenError::NotGraduatable);
+
+        // Buys take tokens out of the pool instead of minting.
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(pool, false));
+        accounts.push(AccountMeta::new_readonly(launch.vault_authority(), false));
+        let (expected, _) = pool_buy(&state, 1_000_000).unwrap();
+        launch.process(buy(1_000_000), accounts).await.unwrap();
+        let supply = launch.mint_supply().await;
+        assert_eq!(supply, 2 * ROUND_VOLUME);
+        assert_eq!(
+            launch.token_balance(launch.payer_tokens()).await,
+            ROUND_VOLUME + expected
+        );
+
+        // Sells put tokens back into the pool.
+        let state = launch.state().await;
+        let (expected, _) = pool_sell(&state, expected).unwrap();
+        let stable_before = launch.token_balance(launch.payer_stable()).await;
+        let mut accounts = launch.burn_accounts();
+        accounts.push(AccountMeta::new(pool, false));
+        launch
+            .process(
+                SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                    _inAmount: ROUND_VOLUME - state.pool_tokens,
+                }),
+                accounts,
+            )
+            .await
+            .unwrap();
+        assert_eq!(launch.mint_supply().await, supply);
+        assert_eq!(launch.state().await.pool_tokens, ROUND_VOLUME);
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            stable_before + expected
+        );
+    }
+
+    /// Buys a launch up to its threshold and graduates it. Returns the pool
+    /// buy accounts.
+    async fn graduated(args: CreateTokenArgs) -> (TestLaunch, Vec<AccountMeta>) {
+        let capped = args.wallet_token_cap > 0;
+        let mut launch = TestLaunch::start(args).await;
+        let pool = get_associated_token_address(&launch.vault_authority(), &launch.mint);
+        let (record, _) = WalletRecord::address(&launch.program_id, &launch.state, &launch.payer());
+        let mut accounts = launch.mint_accounts();
+        if capped {
+            accounts.push(AccountMeta::new(record, false));
+        }
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 100_000_000,
+                }),
+                accounts,
+            )
+            .await
+            .unwrap();
+        launch
+            .process(
+                SplMinterIntstruction::Graduate,
+                vec![
+                    AccountMeta::new(launch.payer(), true),
+                    AccountMeta::new(launch.state, false),
+                    AccountMeta::new(launch.mint, false),
+                    AccountMeta::new_readonly(
+                        Pubkey::find_program_address(&[b"mint_authority"], &launch.program_id).0,
+                        false,
+                    ),
+                    AccountMeta::new(pool, false),
+                    AccountMeta::new_readonly(launch.vault_authority(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new_readonly(spl_token::id(), false),
+                    AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                ],
+            )
+            .await
+            .unwrap();
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(pool, false));
+        accounts.push(AccountMeta::new_readonly(launch.vault_authority(), false));
+        if capped {
+            accounts.push(AccountMeta::new(record, false));
+        }
+        (launch, accounts)
+    }
+
+    #[tokio::test]
+    async fn pool_buys_keep_wallet_caps_and_price_impact() {
+        let pool_args = || {
+            let mut args = create_args(stepped_linear());
+            args.graduation_threshold = ROUND_VOLUME;
+            args.pool_token_allocation = ROUND_VOLUME;
+            args
+        };
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+
+        let mut args = pool_args();
+        // Buying the curve up to the threshold doubles the stepped price.
+        args.max_price_impact = 150 * PERCENT_DENOMINATOR;
+        let (mut launch, accounts) = graduated(args).await;
+        // Doubling the pool stable quadruples its price.
+        let pool_stable = launch.state().await.pool_stable;
+        let result = launch.process(buy(pool_stable), accounts.clone()).await;
+        assert_token_error(result, TokenError::PriceImpactTooHigh);
+        launch.process(buy(100_000), accounts).await.unwrap();
+
+        let cap = ROUND_VOLUME + ROUND_VOLUME / 10;
+        let mut args = pool_args();
+        args.wallet_token_cap = cap;
+        let (mut launch, accounts) = graduated(args).await;
+        // The buy is filled up to the cap.
+        launch
+            .process(buy(pool_stable), accounts.clone())
+            .await
+            .unwrap();
+        assert_eq!(launch.token_balance(launch.payer_tokens()).await, cap);
+        let (record, _) = WalletRecord::address(&launch.program_id, &launch.state, &launch.payer());
+        let stored = WalletRecord::try_from_slice(&launch.account(record).await.data).unwrap();
+        assert_eq!(stored.bought_tokens, cap);
+        assert_eq!(
+            stored.spent_stable,
+            PAYER_STABLE - launch.token_balance(launch.payer_stable()).await
+        );
+        let result = launch.process(buy(100_000), accounts).await;
+        assert_token_error(result, TokenError::WalletCapExceeded);
+    }
+}
@@ -0,0 +1,156 @@
+use super::create::State;
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    mpl_token_metadata::{accounts::Metadata, instructions as mpl_instruction, types::DataV2},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UpdateMetadataArgs {
+    pub token_title: String,
+    pub token_symbol: String,
+    pub token_uri: String,
+}
+
+/// Checks the signer is the claim authority of the launch and `metadata_account`
+/// is the metadata of its mint. Returns the `mint_authority` bump, the update
+/// authority of the metadata.
+fn check_metadata_admin(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    state_account: &AccountInfo,
+    metadata_account: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !admin.is_signer || *admin.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let (metadata_address, _) = Metadata::find_pda(&account_state.mint_account);
+    if metadata_address != *metadata_account.key {
+        return Err(TokenError::WrongMetadataAccount.into());
+    }
+    let (_, bump) = Pubkey::find_program_address(&[b"mint_authority"], program_id);
+    Ok(bump)
+}
+
+/// Replaces the name, symbol and uri of the token. Accounts: claim authority,
+/// state, metadata, mint authority, token metadata program. Fails once the
+/// metadata is locked.
+pub fn update_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateMetadataArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let metadata_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_metadata_program = next_account_info(accounts_iter)?;
+
+    let bump = check_metadata_admin(program_id, admin, state_account, metadata_account)?;
+    mpl_instruction::UpdateMetadataAccountV2CpiBuilder::new(token_metadata_program)
+        .metadata(metadata_account)
+        .update_authority(mint_authority)
+        .data(DataV2 {
+            name: args.token_title,
+            uri: args.token_uri,
+            symbol: args.token_symbol,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        })
+        .invoke_signed(&[&[b"mint_authority", &[bump]]])?;
+
+    msg!("Metadata updated: {}", metadata_account.key);
+    Ok(())
+}
+
+/// Makes the metadata immutable, for good. Same accounts as `UpdateMetadata`.
+pub fn lock_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let metadata_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_metadata_program = next_account_info(accounts_iter)?;
+
+    let bump = check_metadata_admin(program_id, admin, state_account, metadata_account)?;
+    mpl_instruction::UpdateMetadataAccountV2CpiBuilder::new(token_metadata_program)
+        .metadata(metadata_account)
+        .update_authority(mint_authority)
+        .is_mutable(false)
+        .invoke_signed(&[&[b"mint_authority", &[bump]]])?;
+
+    msg!("Metadata locked: {}", metadata_account.key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{processor::SplMinterIntstruction, test_utils::*};
+    use solana_program::instruction::AccountMeta;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn claim_authority_updates_then_locks_metadata() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let (metadata, _) = Metadata::find_pda(&launch.mint);
+        let (mint_authority, _) =
+            Pubkey::find_program_address(&[b"mint_authority"], &launch.program_id);
+        let accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new_readonly(launch.state, false),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(mint_authority, false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+        ];
+        let update = |uri: &str| {
+            SplMinterIntstruction::UpdateMetadata(UpdateMetadataArgs {
+                token_title: "Renamed".to_string(),
+                token_symbol: "RNM".to_string(),
+                token_uri: uri.to_string(),
+            })
+        };
+
+        let stranger = Keypair::new();
+        let mut stranger_accounts = accounts.clone();
+        stranger_accounts[0] = AccountMeta::new_readonly(stranger.pubkey(), true);
+        let result = launch
+            .process_with_signers(update("https://a"), stranger_accounts, &[&stranger])
+            .await;
+        assert_token_error(result, TokenError::Unauthorized);
+
+        launch
+            .process(update("https://a"), accounts.clone())
+            .await
+            .unwrap();
+        let stored = Metadata::from_bytes(&launch.account(metadata).await.data).unwrap();
+        assert_eq!(stored.name.trim_end_matches('\0'), "Renamed");
+        assert_eq!(stored.symbol.trim_end_matches('\0'), "RNM");
+        assert_eq!(stored.uri.trim_end_matches('\0'), "https://a");
+        assert!(stored.is_mutable);
+
+        launch
+            .process(SplMinterIntstruction::LockMetadata, accounts.clone())
+            .await
+            .unwrap();
+        let stored = Metadata::from_bytes(&launch.account(metadata).await.data).unwrap();
+        assert!(!stored.is_mutable);
+        assert!(launch.process(update("https://b"), accounts).await.is_err());
+    }
+}
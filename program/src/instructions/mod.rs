@@ -1,9 +1,47 @@
+pub mod allowlist;
+pub mod buyback;
+pub mod candles;
 pub mod create;
+pub mod creator;
 //pub mod mint;
 pub mod claim;
+pub mod config;
+pub mod freeze;
+pub mod graduate;
+pub mod metadata;
+pub mod oracle;
+pub mod presale;
+pub mod referral;
+pub mod registry;
+pub mod schedule;
+pub mod staking;
+pub mod stats;
+pub mod supply;
 pub mod trade;
+pub mod trading_window;
+pub mod vesting;
+pub mod wallet;
 
+pub use allowlist::*;
+pub use buyback::*;
+pub use candles::*;
 pub use create::*;
+pub use creator::*;
 //pub use mint::*;
 pub use claim::*;
+pub use config::*;
+pub use freeze::*;
+pub use graduate::*;
+pub use metadata::*;
+pub use oracle::*;
+pub use presale::*;
+pub use referral::*;
+pub use registry::*;
+pub use schedule::*;
+pub use staking::*;
+pub use stats::*;
+pub use supply::*;
 pub use trade::*;
+pub use trading_window::*;
+pub use vesting::*;
+pub use wallet::*;
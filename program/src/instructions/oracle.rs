@@ -0,0 +1,276 @@
+use super::create::{create_pda_account, State};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::set_return_data,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct GetTwapArgs {
+    /// Seconds back from now the average should cover.
+    pub window: i64,
+}
+
+/// Returned by `GetTwap` through the return data.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct TwapResult {
+    /// Time-weighted spot price, in stable units per whole token.
+    pub twap: u64,
+    /// Seconds actually covered, at least the requested window.
+    pub window: i64,
+    pub spot_price: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Observation {
+    /// 0 for an entry not written yet.
+    pub timestamp: i64,
+    pub price_cumulative: u128,
+}
+
+/// Ring of `State::price_cumulative` snapshots, at most one per second, stored
+/// at `[b"oracle", state_account]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Oracle {
+    pub state: Pubkey,
+    /// Index the next observation overwrites.
+    pub next: u16,
+    pub observations: Vec<Observation>,
+}
+
+impl Oracle {
+    pub const CAPACITY: usize = 64;
+    pub const LEN: usize = 32 + 2 + 4 + Oracle::CAPACITY * (8 + 16);
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"oracle", state_account.as_ref()], program_id)
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        oracle_account: &AccountInfo,
+    ) -> Result<Oracle, ProgramError> {
+        let (address, _) = Oracle::address(program_id, state_account.key);
+        if address != *oracle_account.key || oracle_account.owner != program_id {
+            return Err(TokenError::WrongOracleAccount.into());
+        }
+        Oracle::try_from_slice(&oracle_account.data.borrow())
+            .map_err(|_| TokenError::WrongOracleAccount.into())
+    }
+
+    fn latest(&self) -> &Observation {
+        let index = (self.next as usize + Oracle::CAPACITY - 1) % Oracle::CAPACITY;
+        &self.observations[index]
+    }
+
+    /// Writes `price_cumulative` at `now` unless this second is already stored.
+    pub fn record(&mut self, now: i64, price_cumulative: u128) {
+        if self.latest().timestamp >= now {
+            return;
+        }
+        self.observations[self.next as usize] = Observation {
+            timestamp: now,
+            price_cumulative,
+        };
+        self.next = ((self.next as usize + 1) % Oracle::CAPACITY) as u16;
+    }
+
+    /// Newest observation at or before `timestamp`.
+    pub fn before(&self, timestamp: i64) -> Option<&Observation> {
+        self.observations
+            .iter()
+            .filter(|observation| observation.timestamp > 0 && observation.timestamp <= timestamp)
+            .max_by_key(|observation| observation.timestamp)
+    }
+}
+
+impl State {
+    /// `price_cumulative` brought forward to `now` at `last_price`.
+    pub fn price_cumulative_at(&self, now: i64) -> u128 {
+        let elapsed = now.saturating_sub(self.price_updated_at).max(0) as u128;
+        self.price_cumulative
+            .wrapping_add(self.last_price as u128 * elapsed)
+    }
+
+    /// Closes the interval priced at `last_price` and opens one at `price`.
+    pub fn update_price(&mut self, now: i64, price: u64) {
+        self.price_cumulative = self.price_cumulative_at(now);
+        self.price_updated_at = now;
+        self.last_price = price;
+    }
+}
+
+/// Reads the observation ring trades pass once `InitOracle` ran.
+pub fn next_oracle<'a, 'b>(
+    program_id: &Pubkey,
+    state_account: &AccountInfo<'a>,
+    account_state: &State,
+    accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+) -> Result<Option<(&'b AccountInfo<'a>, Oracle)>, ProgramError> {
+    if !account_state.oracle_enabled {
+        return Ok(None);
+    }
+    let oracle_account = next_account_info(accounts_iter)?;
+    let oracle = Oracle::load(program_id, state_account, oracle_account)?;
+    Ok(Some((oracle_account, oracle)))
+}
+
+/// Snapshots the accumulator of `account_state` into the ring, if any.
+pub fn save_oracle(
+    oracle: Option<(&AccountInfo, Oracle)>,
+    account_state: &State,
+    now: i64,
+) -> ProgramResult {
+    if let Some((oracle_account, mut oracle)) = oracle {
+        oracle.record(now, account_state.price_cumulative_at(now));
+        oracle.serialize(&mut &mut oracle_account.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+/// Creates the observation ring of a launch. From then on every trade has to
+/// pass it, after the other launch-specific accounts.
+pub fn init_oracle(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let claim_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let oracle_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !claim_account.is_signer || *claim_account.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if account_state.oracle_enabled {
+        return Err(TokenError::OracleInitialized.into());
+    }
+    let (address, bump) = Oracle::address(program_id, state_account.key);
+    if address != *oracle_account.key {
+        return Err(TokenError::WrongOracleAccount.into());
+    }
+
+    create_pda_account(
+        claim_account,
+        oracle_account,
+        Oracle::LEN,
+        program_id,
+        system_program,
+        &[b"oracle", state_account.key.as_ref(), &[bump]],
+    )?;
+    let now = Clock::get()?.unix_timestamp;
+    let mut oracle = Oracle {
+        state: *state_account.key,
+        next: 0,
+        observations: vec![Observation::default(); Oracle::CAPACITY],
+    };
+    oracle.record(now, account_state.price_cumulative_at(now));
+    oracle.serialize(&mut &mut oracle_account.data.borrow_mut()[..])?;
+
+    account_state.oracle_enabled = true;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+    msg!("Oracle: {}", oracle_account.key);
+    Ok(())
+}
+
+/// Returns the spot price averaged since the newest observation at least
+/// `window` seconds old. Accounts: state, oracle.
+pub fn get_twap(program_id: &Pubkey, accounts: &[AccountInfo], args: GetTwapArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let state_account = next_account_info(accounts_iter)?;
+    let oracle_account = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let oracle = Oracle::load(program_id, state_account, oracle_account)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let start = oracle
+        .before(now.saturating_sub(args.window.max(1)))
+        .ok_or(TokenError::TwapUnavailable)?;
+    let window = now - start.timestamp;
+    let twap = account_state
+        .price_cumulative_at(now)
+        .wrapping_sub(start.price_cumulative)
+        / window as u128;
+    let result = TwapResult {
+        twap: u64::try_from(twap).map_err(|_| TokenError::MathOverflow)?,
+        window,
+        spot_price: account_state.last_price,
+    };
+    set_return_data(&result.try_to_vec()?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_program};
+
+    #[tokio::test]
+    async fn twap_weighs_prices_by_time() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let (oracle, _) = Oracle::address(&launch.program_id, &launch.state);
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 4_000_000,
+            })
+        };
+        let mut buy_accounts = launch.mint_accounts();
+        buy_accounts.push(AccountMeta::new(oracle, false));
+        let twap_accounts = vec![
+            AccountMeta::new_readonly(launch.state, false),
+            AccountMeta::new_readonly(oracle, false),
+        ];
+        let get_twap = |window| SplMinterIntstruction::GetTwap(GetTwapArgs { window });
+
+        let start = launch.now().await;
+        launch
+            .process(
+                SplMinterIntstruction::InitOracle,
+                vec![
+                    AccountMeta::new(launch.payer(), true),
+                    AccountMeta::new(launch.state, false),
+                    AccountMeta::new(oracle, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(launch.process(buy(), launch.mint_accounts()).await.is_err());
+
+        // Round 1 price for 100 seconds, then round 3 price for 100 seconds.
+        launch.warp_to(start + 100).await;
+        launch.process(buy(), buy_accounts).await.unwrap();
+        assert_eq!(launch.state().await.last_price, 3);
+        launch.warp_to(start + 200).await;
+
+        let read = |data: Vec<u8>| TwapResult::try_from_slice(&data).unwrap();
+        let recent = read(launch.view(get_twap(100), twap_accounts.clone()).await);
+        assert_eq!((recent.twap, recent.window), (3, 100));
+        let full = read(launch.view(get_twap(150), twap_accounts.clone()).await);
+        assert_eq!((full.twap, full.window), (2, 200));
+        assert_eq!(full.spot_price, 3);
+        let result = launch.process(get_twap(1_000), twap_accounts).await;
+        assert_token_error(result, TokenError::TwapUnavailable);
+    }
+}
@@ -0,0 +1,474 @@
+use super::{
+    create::{create_pda_account, vault_authority, CreateTokenArgs, LaunchStatus, State},
+    schedule::RoundSchedule,
+};
+use crate::{
+    curve::{CurveConfig, CurveKind},
+    error::TokenError,
+};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::instruction as token_instruction,
+};
+
+/// Stable paid and tokens received by one buyer during the presale, stored at
+/// `[b"contribution", state_account, wallet]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contribution {
+    pub state: Pubkey,
+    pub wallet: Pubkey,
+    /// Stable paid, fees included.
+    pub contributed: u64,
+    pub fees: u64,
+    pub tokens: u64,
+}
+
+impl Contribution {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8;
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"contribution", state_account.as_ref(), wallet.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Reads the contribution of `wallet`, creating it on the first buy.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        wallet: &AccountInfo<'a>,
+        contribution_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<Contribution, ProgramError> {
+        if contribution_account.owner == program_id {
+            return Contribution::load(
+                program_id,
+                state_account.key,
+                wallet.key,
+                contribution_account,
+            );
+        }
+        let (address, bump) = Contribution::address(program_id, state_account.key, wallet.key);
+        if address != *contribution_account.key {
+            return Err(TokenError::WrongContributionAccount.into());
+        }
+        create_pda_account(
+            wallet,
+            contribution_account,
+            Contribution::LEN,
+            program_id,
+            system_program,
+            &[
+                b"contribution",
+                state_account.key.as_ref(),
+                wallet.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(Contribution {
+            state: *state_account.key,
+            wallet: *wallet.key,
+            ..Contribution::default()
+        })
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &Pubkey,
+        wallet: &Pubkey,
+        contribution_account: &AccountInfo,
+    ) -> Result<Contribution, ProgramError> {
+        let (address, _) = Contribution::address(program_id, state_account, wallet);
+        if contribution_account.owner != program_id || address != *contribution_account.key {
+            return Err(TokenError::WrongContributionAccount.into());
+        }
+        Contribution::try_from_slice(&contribution_account.data.borrow())
+            .map_err(|_| TokenError::WrongContributionAccount.into())
+    }
+}
+
+pub fn validate_presale(args: &CreateTokenArgs) -> ProgramResult {
+    if args.soft_cap == 0 {
+        return Ok(());
+    }
+    if (args.hard_cap > 0 && args.hard_cap < args.soft_cap)
+        || args.deadline <= Clock::get()?.unix_timestamp
+    {
+        return Err(TokenError::InvalidPresale.into());
+    }
+    // A schedule curve is checked by `SetSchedule`, once it has rounds.
+    if args.curve.kind == CurveKind::Schedule {
+        return Ok(());
+    }
+    check_soft_cap_reachable(
+        &args.curve,
+        None,
+        args.vesting_amount,
+        args.soft_cap,
+        args.graduation_threshold,
+    )
+}
+
+/// Errors when the curve sells out, or reaches `graduation_threshold`, from
+/// `supply` for no more than `soft_cap` stable: the sale would end before the
+/// presale could succeed.
+pub fn check_soft_cap_reachable(
+    curve: &CurveConfig,
+    schedule: Option<&RoundSchedule>,
+    supply: u64,
+    soft_cap: u64,
+    graduation_threshold: u64,
+) -> ProgramResult {
+    let mut max_supply = curve.max_supply(schedule)?;
+    if graduation_threshold > 0 {
+        max_supply = max_supply.min(graduation_threshold);
+    }
+    let (tokens, _) = curve.buy(schedule, supply, soft_cap, 0)?;
+    if tokens >= max_supply.saturating_sub(supply) {
+        return Err(TokenError::InvalidPresale.into());
+    }
+    Ok(())
+}
+
+impl State {
+    /// Buys are collected as contributions and sells are closed until the
+    /// presale settles.
+    pub fn in_presale(&self) -> bool {
+        self.soft_cap > 0 && self.status == LaunchStatus::Active
+    }
+
+    /// Moves an open presale to `Succeeded` or `Failed` once its deadline
+    /// passed. Returns whether the status changed.
+    pub fn settle_presale(&mut self, now: i64) -> bool {
+        if !self.in_presale() || now < self.deadline {
+            return false;
+        }
+        self.status = if self.raised >= self.soft_cap {
+            LaunchStatus::Succeeded
+        } else {
+            LaunchStatus::Failed
+        };
+        msg!(
+            "Presale settled: {:?}, raised: {}",
+            self.status,
+            self.raised
+        );
+        true
+    }
+
+    /// Records the curve reaching `max_supply`. An open presale is not
+    /// completed: it closes as if it hit its hard cap once the soft cap is
+    /// met, and otherwise still settles at its deadline.
+    pub fn complete_sale(&mut self, max_supply: u64) {
+        if !self.in_presale() {
+            self.status = LaunchStatus::SaleCompleted;
+            msg!("SaleCompleted at supply: {}", max_supply);
+        } else if self.raised >= self.soft_cap {
+            self.status = LaunchStatus::Succeeded;
+            msg!("Presale reached max supply: {}", self.raised);
+        }
+    }
+
+    /// Errors unless the curve is open for normal trading.
+    pub fn check_presale_settled(&self) -> ProgramResult {
+        if self.in_presale() {
+            return Err(TokenError::PresaleActive.into());
+        }
+        if self.status == LaunchStatus::Failed {
+            return Err(TokenError::PresaleFailed.into());
+        }
+        Ok(())
+    }
+}
+
+/// Returns a buyer's contribution once the presale failed. Their tokens are
+/// burned and the stable they paid, fees included, goes back to them.
+pub fn refund(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let contribution_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let associated_token_account = next_account_info(accounts_iter)?;
+    let payer_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if !payer.is_signer {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+
+    account_state.settle_presale(Clock::get()?.unix_timestamp);
+    if account_state.status != LaunchStatus::Failed {
+        return Err(TokenError::RefundUnavailable.into());
+    }
+    let mut contribution = Contribution::load(
+        program_id,
+        state_account.key,
+        payer.key,
+        contribution_account,
+    )?;
+    if contribution.contributed == 0 {
+        return Err(TokenError::RefundUnavailable.into());
+    }
+
+    account_state.total -= contribution.fees;
+    account_state.reserve = account_state
+        .reserve
+        .saturating_sub(contribution.contributed - contribution.fees);
+    account_state.raised -= contribution.contributed;
+    account_state.curve_supply -= contribution.tokens;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    let refunded = contribution.contributed;
+    let burned = contribution.tokens;
+    contribution.contributed = 0;
+    contribution.fees = 0;
+    contribution.tokens = 0;
+    contribution.serialize(&mut &mut contribution_account.data.borrow_mut()[..])?;
+
+    invoke(
+        &token_instruction::burn(
+            token_program.key,
+            associated_token_account.key,
+            mint_account.key,
+            payer.key,
+            &[payer.key],
+            burned,
+        )?,
+        &[
+            associated_token_account.clone(),
+            payer.clone(),
+            mint_account.clone(),
+        ],
+    )?;
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            payer_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            refunded,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            payer_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("Refund {} {} {}", payer.key, burned, refunded);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{
+            burnExactTokensForStableArgs, mintTokensForExactStableArgs, ROUND_VOLUME,
+        },
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    fn buy(amount: u64) -> SplMinterIntstruction {
+        SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+            _inAmount: amount,
+        })
+    }
+
+    fn sell(amount: u64) -> SplMinterIntstruction {
+        SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+            _inAmount: amount,
+        })
+    }
+
+    async fn start_presale(soft_cap: u64, hard_cap: u64) -> (TestLaunch, Pubkey, i64) {
+        // Far after the test clock, reached with `warp_to`.
+        let deadline = i64::MAX / 2;
+        let mut args = create_args(stepped_linear());
+        args.soft_cap = soft_cap;
+        args.hard_cap = hard_cap;
+        args.deadline = deadline;
+        let launch = TestLaunch::start(args).await;
+        let (contribution, _) =
+            Contribution::address(&launch.program_id, &launch.state, &launch.payer());
+        (launch, contribution, deadline)
+    }
+
+    fn refund_accounts(launch: &TestLaunch, contribution: Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(contribution, false),
+            AccountMeta::new(launch.mint, false),
+            AccountMeta::new(launch.payer_tokens(), false),
+            AccountMeta::new(launch.payer_stable(), false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new(launch.vault(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]
+    }
+
+    #[tokio::test]
+    async fn missed_soft_cap_refunds_buyers() {
+        let (mut launch, contribution, deadline) = start_presale(1_000_000_000, 0).await;
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(contribution, false));
+        launch
+            .process(buy(1_000_000), accounts.clone())
+            .await
+            .unwrap();
+        launch
+            .process(buy(500_000), accounts.clone())
+            .await
+            .unwrap();
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+
+        let result = launch.process(sell(tokens), launch.burn_accounts()).await;
+        assert_token_error(result, TokenError::PresaleActive);
+        let result = launch
+            .process(
+                SplMinterIntstruction::Refund,
+                refund_accounts(&launch, contribution),
+            )
+            .await;
+        assert_token_error(result, TokenError::RefundUnavailable);
+
+        launch.warp_to(deadline).await;
+        let result = launch.process(buy(1_000_000), accounts).await;
+        assert_token_error(result, TokenError::PresaleFailed);
+        launch
+            .process(
+                SplMinterIntstruction::Refund,
+                refund_accounts(&launch, contribution),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            PAYER_STABLE
+        );
+        assert_eq!(launch.token_balance(launch.payer_tokens()).await, 0);
+        let state = launch.state().await;
+        assert_eq!(state.status, LaunchStatus::Failed);
+        assert_eq!(
+            (state.raised, state.total, state.reserve, state.curve_supply),
+            (0, 0, 0, 0)
+        );
+        let result = launch
+            .process(
+                SplMinterIntstruction::Refund,
+                refund_accounts(&launch, contribution),
+            )
+            .await;
+        assert_token_error(result, TokenError::RefundUnavailable);
+    }
+
+    #[tokio::test]
+    async fn hard_cap_closes_presale() {
+        let (mut launch, contribution, _) = start_presale(1_000_000, 2_000_000).await;
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(contribution, false));
+        launch.process(buy(5_000_000), accounts).await.unwrap();
+
+        let state = launch.state().await;
+        assert_eq!(state.status, LaunchStatus::Succeeded);
+        assert_eq!(state.raised, 2_000_000);
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            PAYER_STABLE - 2_000_000
+        );
+        let result = launch
+            .process(
+                SplMinterIntstruction::Refund,
+                refund_accounts(&launch, contribution),
+            )
+            .await;
+        assert_token_error(result, TokenError::RefundUnavailable);
+
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+        launch
+            .process(sell(tokens / 2), launch.burn_accounts())
+            .await
+            .unwrap();
+        launch
+            .process(buy(1_000_000), launch.mint_accounts())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn presale_selling_out_closes_as_hard_cap() {
+        let args = |soft_cap| {
+            let mut args = create_args(stepped_linear());
+            args.soft_cap = soft_cap;
+            args.deadline = i64::MAX / 2;
+            args.graduation_threshold = ROUND_VOLUME;
+            args.pool_token_allocation = ROUND_VOLUME;
+            args
+        };
+        let mut launch = TestLaunch::new().await;
+        // The threshold costs 1_000_000 before fees.
+        let result = launch.try_create(args(2_000_000)).await;
+        assert_token_error(result, TokenError::InvalidPresale);
+        launch.create(args(500_000)).await;
+        let (contribution, _) =
+            Contribution::address(&launch.program_id, &launch.state, &launch.payer());
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(contribution, false));
+        launch.process(buy(100_000_000), accounts).await.unwrap();
+
+        // Filled up to the threshold, the presale succeeds rather than
+        // completing the sale behind its back.
+        let state = launch.state().await;
+        assert_eq!(state.curve_supply, ROUND_VOLUME);
+        assert_eq!(state.status, LaunchStatus::Succeeded);
+        assert_eq!(
+            state.raised,
+            PAYER_STABLE - launch.token_balance(launch.payer_stable()).await
+        );
+        let result = launch
+            .process(
+                SplMinterIntstruction::Refund,
+                refund_accounts(&launch, contribution),
+            )
+            .await;
+        assert_token_error(result, TokenError::RefundUnavailable);
+    }
+}
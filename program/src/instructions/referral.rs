@@ -0,0 +1,407 @@
+use super::{
+    create::{create_pda_account, vault_authority, State},
+    stats::{next_user_stats, UserStats},
+    trade::{mul_div_u64, PERCENT_DENOMINATOR},
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+/// Highest `referral_share` a launch can set, 50% of the protocol fee.
+pub const MAX_REFERRAL_SHARE: u64 = 50 * PERCENT_DENOMINATOR;
+
+pub fn validate_referral_share(referral_share: u64) -> ProgramResult {
+    if referral_share > MAX_REFERRAL_SHARE {
+        return Err(TokenError::InvalidReferralShare.into());
+    }
+    Ok(())
+}
+
+/// Fees owed to one referrer, stored at `[b"referral", state_account, referrer]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Referral {
+    pub state: Pubkey,
+    pub referrer: Pubkey,
+    /// Stable waiting in the vault for `ClaimReferral`.
+    pub pending: u64,
+    /// Stable credited over all time.
+    pub earned: u64,
+}
+
+impl Referral {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"referral", state_account.as_ref(), referrer.as_ref()],
+            program_id,
+        )
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        referrer: &Pubkey,
+        referral_account: &AccountInfo,
+    ) -> Result<Referral, ProgramError> {
+        let (address, _) = Referral::address(program_id, state_account.key, referrer);
+        if address != *referral_account.key || referral_account.owner != program_id {
+            return Err(TokenError::WrongReferralAccount.into());
+        }
+        Referral::try_from_slice(&referral_account.data.borrow())
+            .map_err(|_| TokenError::WrongReferralAccount.into())
+    }
+
+    /// Reads the account of `referrer`, created by the first trade naming it
+    /// and paid by `trader`.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        referrer: &AccountInfo<'a>,
+        referral_account: &AccountInfo<'a>,
+        trader: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<Referral, ProgramError> {
+        if referral_account.owner == program_id {
+            return Referral::load(program_id, state_account, referrer.key, referral_account);
+        }
+        let (address, bump) = Referral::address(program_id, state_account.key, referrer.key);
+        if address != *referral_account.key {
+            return Err(TokenError::WrongReferralAccount.into());
+        }
+        create_pda_account(
+            trader,
+            referral_account,
+            Referral::LEN,
+            program_id,
+            system_program,
+            &[
+                b"referral",
+                state_account.key.as_ref(),
+                referrer.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(Referral {
+            state: *state_account.key,
+            referrer: *referrer.key,
+            ..Referral::default()
+        })
+    }
+}
+
+/// Optional accounts closing a trade, both may be left out.
+pub type TradeExtras<'a, 'b> = (
+    Option<(&'b AccountInfo<'a>, UserStats)>,
+    Option<(&'b AccountInfo<'a>, Referral)>,
+);
+
+/// Reads the optional end of a trade: the trader's `UserStats`, then a
+/// referrer followed by its `Referral`. Sells carry no system program, so
+/// theirs (`system_program` is `None`) starts with it when anything follows.
+///
+/// A referrer needs the trader's `UserStats`: it is bound on the trader's
+/// first tracked trade and every later trade may only name the same one.
+/// This is best-effort, nothing stops one person from trading through a
+/// second wallet that names the first.
+pub fn next_trade_extras<'a, 'b>(
+    program_id: &Pubkey,
+    state_account: &AccountInfo<'a>,
+    trader: &AccountInfo<'a>,
+    accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    system_program: Option<&'b AccountInfo<'a>>,
+) -> Result<TradeExtras<'a, 'b>, ProgramError> {
+    let Some(system_program) = system_program.or_else(|| accounts_iter.next()) else {
+        return Ok((None, None));
+    };
+    let mut stats = next_user_stats(
+        program_id,
+        state_account,
+        trader,
+        accounts_iter,
+        system_program,
+    )?;
+    let Some(referrer) = accounts_iter.next() else {
+        return Ok((stats, None));
+    };
+    let Some((_, user_stats)) = &mut stats else {
+        return Err(TokenError::InvalidReferrer.into());
+    };
+    if user_stats.trade_count == 0 && user_stats.referrer == Pubkey::default() {
+        user_stats.referrer = *referrer.key;
+    }
+    if referrer.key == trader.key || *referrer.key != user_stats.referrer {
+        return Err(TokenError::InvalidReferrer.into());
+    }
+    let referral_account = next_account_info(accounts_iter)?;
+    let referral = Referral::load_or_create(
+        program_id,
+        state_account,
+        referrer,
+        referral_account,
+        trader,
+        system_program,
+    )?;
+    Ok((stats, Some((referral_account, referral))))
+}
+
+impl State {
+    /// Part of `fee` owed to a referrer.
+    pub fn referral_cut(&self, fee: u64) -> u64 {
+        mul_div_u64(fee, self.referral_share, 100 * PERCENT_DENOMINATOR).unwrap_or(0)
+    }
+}
+
+/// Credits the referrer, if any, with its cut of `fee` and returns the cut.
+pub fn credit_referral(
+    referral: &mut Option<(&AccountInfo, Referral)>,
+    account_state: &State,
+    fee: u64,
+) -> u64 {
+    let Some((_, referral)) = referral else {
+        return 0;
+    };
+    let cut = account_state.referral_cut(fee);
+    referral.pending += cut;
+    referral.earned += cut;
+    msg!("Referral {} {}", referral.referrer, cut);
+    cut
+}
+
+pub fn save_referral(referral: Option<(&AccountInfo, Referral)>) -> ProgramResult {
+    if let Some((referral_account, referral)) = referral {
+        referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+/// Pays a referrer everything pending. Accounts: referrer, state, referral,
+/// vault, referrer stable account, vault authority, stable mint, system,
+/// token and associated token programs.
+pub fn claim_referral(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let referrer = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let referral_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let referrer_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let trade_token = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    if !referrer.is_signer {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let mut referral = Referral::load(program_id, state_account, referrer.key, referral_account)?;
+    if referral.pending == 0 {
+        return Err(TokenError::NothingToClaim.into());
+    }
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+
+    let amount = referral.pending;
+    referral.pending = 0;
+    referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+
+    if referrer_trade_token_ata_account.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                referrer.key,
+                referrer.key,
+                trade_token.key,
+                token_program.key,
+            ),
+            &[
+                trade_token.clone(),
+                referrer_trade_token_ata_account.clone(),
+                referrer.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            referrer_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            amount,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            referrer_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("Referral claimed {} {}", referrer.key, amount);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs},
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn referrers_earn_and_claim_a_fee_share() {
+        assert!(validate_referral_share(MAX_REFERRAL_SHARE).is_ok());
+        assert!(validate_referral_share(MAX_REFERRAL_SHARE + 1).is_err());
+        let mut args = create_args(stepped_linear());
+        args.referral_share = 20 * PERCENT_DENOMINATOR;
+        let mut launch = TestLaunch::start(args).await;
+        let referrer = Keypair::new();
+        let (referral, _) =
+            Referral::address(&launch.program_id, &launch.state, &referrer.pubkey());
+        let referrer_stable = get_associated_token_address(&referrer.pubkey(), &launch.stable_mint);
+        let payer = launch.payer();
+        launch
+            .send(
+                &[system_instruction::transfer(
+                    &payer,
+                    &referrer.pubkey(),
+                    1_000_000_000,
+                )],
+                &[],
+            )
+            .await
+            .unwrap();
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 1_000_000,
+            })
+        };
+        let read = |data: Vec<u8>| Referral::try_from_slice(&data).unwrap();
+        let (stats, _) = UserStats::address(&launch.program_id, &launch.state, &payer);
+        let mint_accounts = launch.mint_accounts();
+        let referred_buy = |referrer: Pubkey, referral: Pubkey, with_stats: bool| {
+            let mut accounts = mint_accounts.clone();
+            if with_stats {
+                accounts.push(AccountMeta::new(stats, false));
+            }
+            accounts.push(AccountMeta::new_readonly(referrer, false));
+            accounts.push(AccountMeta::new(referral, false));
+            accounts
+        };
+
+        let (self_referral, _) = Referral::address(&launch.program_id, &launch.state, &payer);
+        let result = launch
+            .process(buy(), referred_buy(payer, self_referral, true))
+            .await;
+        assert_token_error(result, TokenError::InvalidReferrer);
+        // Without stats there is nothing to bind the referrer to.
+        let result = launch
+            .process(buy(), referred_buy(referrer.pubkey(), referral, false))
+            .await;
+        assert_token_error(result, TokenError::InvalidReferrer);
+
+        launch
+            .process(buy(), referred_buy(referrer.pubkey(), referral, true))
+            .await
+            .unwrap();
+        let bound = UserStats::try_from_slice(&launch.account(stats).await.data).unwrap();
+        assert_eq!(bound.referrer, referrer.pubkey());
+        // The first referrer sticks.
+        let other = Pubkey::new_unique();
+        let (other_referral, _) = Referral::address(&launch.program_id, &launch.state, &other);
+        let result = launch
+            .process(buy(), referred_buy(other, other_referral, true))
+            .await;
+        assert_token_error(result, TokenError::InvalidReferrer);
+        let state = launch.state().await;
+        let fee = 1_000_000 - state.reserve;
+        let bought = read(launch.account(referral).await.data);
+        assert_eq!(bought.referrer, referrer.pubkey());
+        assert_eq!(bought.pending, fee / 5);
+        assert_eq!(state.total, fee - fee / 5);
+
+        let mut sell_accounts = launch.burn_accounts();
+        sell_accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        sell_accounts.push(AccountMeta::new(stats, false));
+        sell_accounts.push(AccountMeta::new_readonly(referrer.pubkey(), false));
+        sell_accounts.push(AccountMeta::new(referral, false));
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+        launch
+            .process(
+                SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                    _inAmount: tokens,
+                }),
+                sell_accounts,
+            )
+            .await
+            .unwrap();
+        let sold = read(launch.account(referral).await.data);
+        let sell_fee = launch.state().await.total - state.total + sold.pending - bought.pending;
+        assert_eq!(sold.pending - bought.pending, sell_fee / 5);
+        assert_eq!(sold.earned, sold.pending);
+
+        let claim_accounts = vec![
+            AccountMeta::new(referrer.pubkey(), true),
+            AccountMeta::new_readonly(launch.state, false),
+            AccountMeta::new(referral, false),
+            AccountMeta::new(launch.vault(), false),
+            AccountMeta::new(referrer_stable, false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new_readonly(launch.stable_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        launch
+            .process_with_signers(
+                SplMinterIntstruction::ClaimReferral,
+                claim_accounts.clone(),
+                &[&referrer],
+            )
+            .await
+            .unwrap();
+        assert_eq!(launch.token_balance(referrer_stable).await, sold.pending);
+        let claimed = read(launch.account(referral).await.data);
+        assert_eq!((claimed.pending, claimed.earned), (0, sold.earned));
+        let result = launch
+            .process_with_signers(
+                SplMinterIntstruction::ClaimReferral,
+                claim_accounts,
+                &[&referrer],
+            )
+            .await;
+        assert_token_error(result, TokenError::NothingToClaim);
+    }
+}
@@ -0,0 +1,284 @@
+use super::create::{create_pda_account, LaunchStatus, State};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+pub const REGISTRY_PAGE_SIZE: u64 = 32;
+
+/// Number of launches ever created, stored at `[b"registry"]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registry {
+    pub launch_count: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaunchRecord {
+    /// Default for an entry not written yet.
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    /// `CreateTokenArgs::creator`, or the payer when no creator is set.
+    pub creator: Pubkey,
+    pub trade_token: Pubkey,
+    pub created_slot: u64,
+    /// Status when created, brought up to date by `SyncRegistryStatus`.
+    pub status: LaunchStatus,
+}
+
+/// `REGISTRY_PAGE_SIZE` launches in creation order, stored at
+/// `[b"registry", page as u64 little endian]`. Launch `n` is entry
+/// `n % REGISTRY_PAGE_SIZE` of page `n / REGISTRY_PAGE_SIZE`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RegistryPage {
+    pub page: u64,
+    pub records: Vec<LaunchRecord>,
+}
+
+impl Registry {
+    pub const LEN: usize = 8;
+
+    pub fn address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"registry"], program_id)
+    }
+
+    /// Reads the registry, created by the first launch and paid by `payer`.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        registry_account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<Registry, ProgramError> {
+        let (address, bump) = Registry::address(program_id);
+        if address != *registry_account.key {
+            return Err(TokenError::WrongRegistryAccount.into());
+        }
+        if registry_account.owner == program_id {
+            return Registry::try_from_slice(&registry_account.data.borrow())
+                .map_err(|_| TokenError::WrongRegistryAccount.into());
+        }
+        create_pda_account(
+            payer,
+            registry_account,
+            Registry::LEN,
+            program_id,
+            system_program,
+            &[b"registry", &[bump]],
+        )?;
+        Ok(Registry::default())
+    }
+}
+
+impl RegistryPage {
+    pub const LEN: usize = 8 + 4 + REGISTRY_PAGE_SIZE as usize * (32 * 4 + 8 + 1);
+
+    pub fn address(program_id: &Pubkey, page: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"registry", &page.to_le_bytes()], program_id)
+    }
+
+    /// Reads a page fetched off-chain.
+    pub fn decode(data: &[u8]) -> Result<RegistryPage, ProgramError> {
+        RegistryPage::try_from_slice(data).map_err(|_| TokenError::WrongRegistryAccount.into())
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        page: u64,
+        page_account: &AccountInfo,
+    ) -> Result<RegistryPage, ProgramError> {
+        let (address, _) = RegistryPage::address(program_id, page);
+        if address != *page_account.key || page_account.owner != program_id {
+            return Err(TokenError::WrongRegistryAccount.into());
+        }
+        RegistryPage::decode(&page_account.data.borrow())
+    }
+
+    /// Reads page `page`, created by its first launch and paid by `payer`.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        page: u64,
+        page_account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<RegistryPage, ProgramError> {
+        if page_account.owner == program_id {
+            return RegistryPage::load(program_id, page, page_account);
+        }
+        let (address, bump) = RegistryPage::address(program_id, page);
+        if address != *page_account.key {
+            return Err(TokenError::WrongRegistryAccount.into());
+        }
+        create_pda_account(
+            payer,
+            page_account,
+            RegistryPage::LEN,
+            program_id,
+            system_program,
+            &[b"registry", &page.to_le_bytes(), &[bump]],
+        )?;
+        Ok(RegistryPage {
+            page,
+            records: vec![LaunchRecord::default(); REGISTRY_PAGE_SIZE as usize],
+        })
+    }
+
+    /// Written records, in creation order.
+    pub fn launches(&self) -> Vec<LaunchRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.state != Pubkey::default())
+            .copied()
+            .collect()
+    }
+}
+
+/// Appends `record` to the registry and returns its index. `page_account`
+/// has to be the page the next launch falls in.
+pub fn register_launch<'a>(
+    program_id: &Pubkey,
+    registry_account: &AccountInfo<'a>,
+    page_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    record: LaunchRecord,
+) -> Result<u64, ProgramError> {
+    let mut registry =
+        Registry::load_or_create(program_id, registry_account, payer, system_program)?;
+    let index = registry.launch_count;
+    let mut page = RegistryPage::load_or_create(
+        program_id,
+        index / REGISTRY_PAGE_SIZE,
+        page_account,
+        payer,
+        system_program,
+    )?;
+    page.records[(index % REGISTRY_PAGE_SIZE) as usize] = record;
+    page.serialize(&mut &mut page_account.data.borrow_mut()[..])?;
+    registry.launch_count += 1;
+    registry.serialize(&mut &mut registry_account.data.borrow_mut()[..])?;
+    msg!("Registered launch {}: {}", index, record.state);
+    Ok(index)
+}
+
+/// Copies the current status of a launch, presale settled, into its
+/// registry record. Anyone can call it. Accounts: state, registry page.
+pub fn sync_registry_status(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let state_account = next_account_info(accounts_iter)?;
+    let page_account = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    account_state.settle_presale(Clock::get()?.unix_timestamp);
+    let index = account_state.registry_index;
+    let mut page = RegistryPage::load(program_id, index / REGISTRY_PAGE_SIZE, page_account)?;
+    let record = &mut page.records[(index % REGISTRY_PAGE_SIZE) as usize];
+    if record.state != *state_account.key {
+        return Err(TokenError::WrongRegistryAccount.into());
+    }
+    record.status = account_state.status;
+    page.serialize(&mut &mut page_account.data.borrow_mut()[..])?;
+    msg!(
+        "Registry status {}: {:?}",
+        state_account.key,
+        account_state.status
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{processor::SplMinterIntstruction, test_utils::*};
+    use solana_program::{instruction::AccountMeta, system_instruction};
+
+    #[tokio::test]
+    async fn launches_are_listed_in_pages() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let first = (launch.state, launch.mint);
+        let now = launch.now().await;
+        let mut args = create_args(stepped_linear());
+        args.soft_cap = 1_000_000;
+        args.deadline = now + 100;
+        launch.create(args).await;
+
+        let (registry, _) = Registry::address(&launch.program_id);
+        let (page, _) = RegistryPage::address(&launch.program_id, 0);
+        let stored = Registry::try_from_slice(&launch.account(registry).await.data).unwrap();
+        assert_eq!(stored.launch_count, 2);
+        let records = RegistryPage::decode(&launch.account(page).await.data)
+            .unwrap()
+            .launches();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].state, records[0].mint), first);
+        assert_eq!(
+            (records[1].state, records[1].mint),
+            (launch.state, launch.mint)
+        );
+        assert_eq!(records[1].creator, launch.payer());
+        assert_eq!(records[1].trade_token, launch.stable_mint);
+        assert_eq!(records[1].status, LaunchStatus::Active);
+        assert_eq!(launch.state().await.registry_index, 1);
+
+        // The presale misses its soft cap.
+        launch.warp_to(now + 200).await;
+        launch
+            .process(
+                SplMinterIntstruction::SyncRegistryStatus,
+                vec![
+                    AccountMeta::new_readonly(launch.state, false),
+                    AccountMeta::new(page, false),
+                ],
+            )
+            .await
+            .unwrap();
+        let records = RegistryPage::decode(&launch.account(page).await.data)
+            .unwrap()
+            .launches();
+        assert_eq!(records[1].status, LaunchStatus::Failed);
+        assert_eq!(records[0].status, LaunchStatus::Active);
+    }
+
+    #[tokio::test]
+    async fn prefunded_registry_accounts_do_not_block_launches() {
+        let mut launch = TestLaunch::new().await;
+        let (registry, _) = Registry::address(&launch.program_id);
+        let (page, _) = RegistryPage::address(&launch.program_id, 0);
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        let payer = launch.payer();
+        launch
+            .send(
+                &[
+                    system_instruction::transfer(&payer, &registry, lamports),
+                    system_instruction::transfer(&payer, &page, lamports),
+                ],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        launch.create(create_args(stepped_linear())).await;
+        let stored = Registry::try_from_slice(&launch.account(registry).await.data).unwrap();
+        assert_eq!(stored.launch_count, 1);
+        let records = RegistryPage::decode(&launch.account(page).await.data)
+            .unwrap()
+            .launches();
+        assert_eq!(records[0].state, launch.state);
+    }
+}
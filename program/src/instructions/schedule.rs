@@ -0,0 +1,284 @@
+use super::{
+    create::{create_pda_account, State},
+    presale::check_soft_cap_reachable,
+};
+use crate::{
+    curve::{segmented_buy, segmented_sell, CurveKind, Segments},
+    error::TokenError,
+    instructions::trade::MAX_TOTAL_SUPPLY,
+};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+pub const MAX_SCHEDULE_ROUNDS: usize = 64;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleRound {
+    /// Curve supply at which the round is sold out.
+    pub end_supply: u64,
+    /// Stable units per whole token inside the round.
+    pub price: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetScheduleArgs {
+    pub rounds: Vec<ScheduleRound>,
+}
+
+/// Round table used by `CurveKind::Schedule`, stored at
+/// `[b"schedule", state_account]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RoundSchedule {
+    pub state: Pubkey,
+    pub rounds: Vec<ScheduleRound>,
+}
+
+impl RoundSchedule {
+    pub fn len(rounds: usize) -> usize {
+        32 + 4 + rounds * 16
+    }
+
+    /// Boundaries must be strictly increasing and prices must never drop.
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if self.rounds.is_empty() || self.rounds.len() > MAX_SCHEDULE_ROUNDS {
+            return Err(TokenError::InvalidSchedule.into());
+        }
+        let mut last = ScheduleRound {
+            end_supply: 0,
+            price: 1,
+        };
+        for round in &self.rounds {
+            if round.end_supply <= last.end_supply || round.price < last.price {
+                return Err(TokenError::InvalidSchedule.into());
+            }
+            last = *round;
+        }
+        if last.end_supply > MAX_TOTAL_SUPPLY {
+            return Err(TokenError::InvalidSchedule.into());
+        }
+        Ok(())
+    }
+
+    pub fn max_supply(&self) -> u64 {
+        self.rounds.last().map_or(0, |round| round.end_supply)
+    }
+
+    /// Fills at most up to `max_supply`.
+    pub fn buy(&self, supply: u64, amount: u64) -> Result<u64, ProgramError> {
+        segmented_buy(self, supply, amount).ok_or_else(|| TokenError::MathOverflow.into())
+    }
+
+    pub fn sell(&self, supply: u64, amount: u64) -> Result<u64, ProgramError> {
+        segmented_sell(self, supply, amount).ok_or_else(|| TokenError::MathOverflow.into())
+    }
+
+    pub fn spot_price(&self, supply: u64) -> Result<u64, ProgramError> {
+        let segment = self
+            .segment(supply)
+            .or_else(|| self.segment(supply.checked_sub(1)?));
+        segment
+            .map(|(_, _, price)| price)
+            .ok_or_else(|| TokenError::MAX_TOTAL_SUPPLY.into())
+    }
+
+    /// Round priced by `spot_price` at `supply`, counted from 1.
+    pub fn round(&self, supply: u64) -> u64 {
+        let index = self
+            .rounds
+            .partition_point(|round| round.end_supply <= supply);
+        index.min(self.rounds.len().saturating_sub(1)) as u64 + 1
+    }
+
+    /// Reads the schedule of `state_account`, checking that it belongs to it.
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        schedule_account: &AccountInfo,
+    ) -> Result<RoundSchedule, ProgramError> {
+        let (schedule_pda, _) =
+            Pubkey::find_program_address(&[b"schedule", state_account.key.as_ref()], program_id);
+        if schedule_account.owner != program_id || schedule_pda != *schedule_account.key {
+            return Err(TokenError::WrongScheduleAccount.into());
+        }
+        let schedule = RoundSchedule::try_from_slice(&schedule_account.data.borrow())?;
+        if schedule.state != *state_account.key {
+            return Err(TokenError::WrongScheduleAccount.into());
+        }
+        Ok(schedule)
+    }
+}
+
+/// Reads the trailing schedule account of a trade instruction. It is only
+/// expected when the launch uses `CurveKind::Schedule`.
+pub fn next_schedule(
+    program_id: &Pubkey,
+    state_account: &AccountInfo,
+    account_state: &State,
+    accounts_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<Option<RoundSchedule>, ProgramError> {
+    if account_state.curve.kind != CurveKind::Schedule {
+        return Ok(None);
+    }
+    let schedule_account = next_account_info(accounts_iter)?;
+    RoundSchedule::load(program_id, state_account, schedule_account).map(Some)
+}
+
+impl Segments for RoundSchedule {
+    fn segment(&self, supply: u64) -> Option<(u64, u64, u64)> {
+        let index = self
+            .rounds
+            .partition_point(|round| round.end_supply <= supply);
+        let round = self.rounds.get(index)?;
+        let start = if index == 0 {
+            0
+        } else {
+            self.rounds[index - 1].end_supply
+        };
+        Some((start, round.end_supply, round.price))
+    }
+}
+
+pub fn set_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetScheduleArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let claim_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let schedule_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+
+    if !claim_account.is_signer || *claim_account.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if account_state.curve.kind != CurveKind::Schedule || account_state.curve_supply != 0 {
+        return Err(TokenError::InvalidSchedule.into());
+    }
+
+    let schedule = RoundSchedule {
+        state: *state_account.key,
+        rounds: args.rounds,
+    };
+    schedule.validate()?;
+    if account_state.soft_cap > 0 {
+        check_soft_cap_reachable(
+            &account_state.curve,
+            Some(&schedule),
+            0,
+            account_state.soft_cap,
+            account_state.graduation_threshold,
+        )?;
+    }
+
+    let (schedule_pda, schedule_bump) =
+        Pubkey::find_program_address(&[b"schedule", state_account.key.as_ref()], program_id);
+    if schedule_pda != *schedule_account.key {
+        return Err(TokenError::WrongScheduleAccount.into());
+    }
+
+    let len = RoundSchedule::len(schedule.rounds.len());
+    msg!(
+        "Creating schedule account with {} rounds",
+        schedule.rounds.len()
+    );
+    create_pda_account(
+        claim_account,
+        schedule_account,
+        len,
+        program_id,
+        system_program,
+        &[b"schedule", state_account.key.as_ref(), &[schedule_bump]],
+    )?;
+    schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+    account_state.update_price(Clock::get()?.unix_timestamp, schedule.spot_price(0)?);
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        curve::CurveConfig,
+        processor::SplMinterIntstruction,
+        test_utils::{create_args, TestLaunch},
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+
+    #[tokio::test]
+    async fn prefunded_schedule_account_does_not_block_set_schedule() {
+        let mut launch = TestLaunch::start(create_args(CurveConfig {
+            kind: CurveKind::Schedule,
+            param_a: 0,
+            param_b: 0,
+        }))
+        .await;
+        let (schedule, _) =
+            Pubkey::find_program_address(&[b"schedule", launch.state.as_ref()], &launch.program_id);
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        let payer = launch.payer();
+        launch
+            .send(
+                &[system_instruction::transfer(&payer, &schedule, lamports)],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let rounds = vec![
+            ScheduleRound {
+                end_supply: 1_000_000,
+                price: 10,
+            },
+            ScheduleRound {
+                end_supply: 2_000_000,
+                price: 20,
+            },
+        ];
+        let state = launch.state;
+        launch
+            .process(
+                SplMinterIntstruction::SetSchedule(SetScheduleArgs {
+                    rounds: rounds.clone(),
+                }),
+                vec![
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new(state, false),
+                    AccountMeta::new(schedule, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let account = launch.account(schedule).await;
+        assert_eq!(account.owner, launch.program_id);
+        let stored = RoundSchedule::try_from_slice(&account.data).unwrap();
+        assert_eq!(stored, RoundSchedule { state, rounds });
+        assert_eq!(launch.state().await.last_price, 10);
+    }
+}
@@ -0,0 +1,540 @@
+use super::{
+    create::{create_pda_account, vault_authority, CreateTokenArgs, State},
+    creator::BPS_DENOMINATOR,
+    trade::mul_div_u64,
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+/// Scale of `State::reward_per_token`, large enough that a small fee over a
+/// whole token supply staked still moves it.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct StakeArgs {
+    pub amount: u64,
+}
+
+/// Tokens staked by one holder, stored at `[b"stake", state, staker]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeAccount {
+    pub state: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    /// `State::reward_per_token` when `rewards` was last brought up to date.
+    pub reward_per_token_paid: u128,
+    /// Stable earned and not claimed yet.
+    pub rewards: u64,
+}
+
+impl StakeAccount {
+    pub const LEN: usize = 32 + 32 + 8 + 16 + 8;
+
+    pub fn address(program_id: &Pubkey, state: &Pubkey, staker: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"stake", state.as_ref(), staker.as_ref()], program_id)
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        staker: &Pubkey,
+        stake_account: &AccountInfo,
+    ) -> Result<StakeAccount, ProgramError> {
+        let (address, _) = StakeAccount::address(program_id, state_account.key, staker);
+        if address != *stake_account.key || stake_account.owner != program_id {
+            return Err(TokenError::WrongStakeAccount.into());
+        }
+        StakeAccount::try_from_slice(&stake_account.data.borrow())
+            .map_err(|_| TokenError::WrongStakeAccount.into())
+    }
+
+    /// Reads the account of `staker`, created by their first stake.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        staker: &AccountInfo<'a>,
+        stake_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<StakeAccount, ProgramError> {
+        if stake_account.owner == program_id {
+            return StakeAccount::load(program_id, state_account, staker.key, stake_account);
+        }
+        let (address, bump) = StakeAccount::address(program_id, state_account.key, staker.key);
+        if address != *stake_account.key {
+            return Err(TokenError::WrongStakeAccount.into());
+        }
+        create_pda_account(
+            staker,
+            stake_account,
+            StakeAccount::LEN,
+            program_id,
+            system_program,
+            &[
+                b"stake",
+                state_account.key.as_ref(),
+                staker.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(StakeAccount {
+            state: *state_account.key,
+            staker: *staker.key,
+            ..StakeAccount::default()
+        })
+    }
+
+    /// Adds what the stake earned since it was last settled to `rewards`.
+    pub fn settle(&mut self, account_state: &State) {
+        let earned = self.amount as u128
+            * (account_state.reward_per_token - self.reward_per_token_paid)
+            / REWARD_PRECISION;
+        self.rewards += earned as u64;
+        self.reward_per_token_paid = account_state.reward_per_token;
+    }
+}
+
+/// Owner of the token account holding the staked tokens.
+pub fn staking_authority(program_id: &Pubkey, state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"staking", state.as_ref()], program_id)
+}
+
+pub fn validate_staking(args: &CreateTokenArgs) -> ProgramResult {
+    if args.staking_fee_bps > BPS_DENOMINATOR {
+        return Err(TokenError::InvalidStakingFee.into());
+    }
+    Ok(())
+}
+
+impl State {
+    /// Moves the stakers' part of a trade `fee` out of `total` into the
+    /// reward accumulator and returns it. Only what the accumulator credits
+    /// is moved, the rounding stays in `total`. Nothing is shared while no
+    /// tokens are staked.
+    pub fn share_with_stakers(&mut self, fee: u64) -> u64 {
+        if self.total_staked == 0 {
+            return 0;
+        }
+        let share = mul_div_u64(fee, self.staking_fee_bps, BPS_DENOMINATOR).unwrap_or(0);
+        let total_staked = self.total_staked as u128;
+        let increment = share as u128 * REWARD_PRECISION / total_staked;
+        let credited = (increment * total_staked / REWARD_PRECISION) as u64;
+        self.reward_per_token += increment;
+        self.staking_rewards += credited;
+        credited
+    }
+}
+
+fn check_staking_vault(
+    program_id: &Pubkey,
+    state_account: &AccountInfo,
+    account_state: &State,
+    staking_vault: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (authority, bump) = staking_authority(program_id, state_account.key);
+    if get_associated_token_address(&authority, &account_state.mint_account) != *staking_vault.key {
+        return Err(TokenError::WrongStakeAccount.into());
+    }
+    Ok(bump)
+}
+
+/// Deposits launch tokens into the staking vault. Accounts: staker, state,
+/// stake account, staker token account, staking vault, staking authority,
+/// mint, system program, token program, associated token program.
+pub fn stake(program_id: &Pubkey, accounts: &[AccountInfo], args: StakeArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staker = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let stake_account = next_account_info(accounts_iter)?;
+    let staker_token_account = next_account_info(accounts_iter)?;
+    let staking_vault = next_account_info(accounts_iter)?;
+    let staking_authority_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    if !staker.is_signer {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+    check_staking_vault(program_id, state_account, &account_state, staking_vault)?;
+    if args.amount == 0 {
+        return Err(TokenError::ZeroStake.into());
+    }
+    let mut stake = StakeAccount::load_or_create(
+        program_id,
+        state_account,
+        staker,
+        stake_account,
+        system_program,
+    )?;
+
+    if staking_vault.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                staker.key,
+                staking_authority_account.key,
+                mint_account.key,
+                token_program.key,
+            ),
+            &[
+                staker.clone(),
+                staking_vault.clone(),
+                staking_authority_account.clone(),
+                mint_account.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke(
+        &token_instruction::transfer(
+            token_program.key,
+            staker_token_account.key,
+            staking_vault.key,
+            staker.key,
+            &[],
+            args.amount,
+        )?,
+        &[
+            staker_token_account.clone(),
+            staking_vault.clone(),
+            staker.clone(),
+        ],
+    )?;
+
+    stake.settle(&account_state);
+    stake.amount += args.amount;
+    account_state.total_staked += args.amount;
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Staked {} {}, total: {}",
+        staker.key,
+        args.amount,
+        stake.amount
+    );
+    Ok(())
+}
+
+/// Withdraws staked tokens. Accounts: staker, state, stake account, staker
+/// token account, staking vault, staking authority, token program.
+pub fn unstake(program_id: &Pubkey, accounts: &[AccountInfo], args: StakeArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staker = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let stake_account = next_account_info(accounts_iter)?;
+    let staker_token_account = next_account_info(accounts_iter)?;
+    let staking_vault = next_account_info(accounts_iter)?;
+    let staking_authority_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    if !staker.is_signer {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let bump = check_staking_vault(program_id, state_account, &account_state, staking_vault)?;
+    let mut stake = StakeAccount::load(program_id, state_account, staker.key, stake_account)?;
+    if args.amount == 0 || args.amount > stake.amount {
+        return Err(TokenError::ZeroStake.into());
+    }
+
+    stake.settle(&account_state);
+    stake.amount -= args.amount;
+    account_state.total_staked -= args.amount;
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            staking_vault.key,
+            staker_token_account.key,
+            staking_authority_account.key,
+            &[],
+            args.amount,
+        )?,
+        &[
+            staking_vault.clone(),
+            staker_token_account.clone(),
+            staking_authority_account.clone(),
+        ],
+        &[&[b"staking", state_account.key.as_ref(), &[bump]]],
+    )?;
+
+    msg!(
+        "Unstaked {} {}, left: {}",
+        staker.key,
+        args.amount,
+        stake.amount
+    );
+    Ok(())
+}
+
+/// Pays a staker the fees their stake earned. Accounts: staker, state, stake
+/// account, vault, staker trade token account, vault authority, trade token,
+/// system program, token program, associated token program.
+pub fn claim_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let staker = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let stake_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let staker_trade_token_ata_account = next_account_info(accounts_iter)?;
+    let pda_trade_token_authority = next_account_info(accounts_iter)?;
+    let trade_token = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    if !staker.is_signer {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+    let mut stake = StakeAccount::load(program_id, state_account, staker.key, stake_account)?;
+    stake.settle(&account_state);
+    if stake.rewards == 0 {
+        return Err(TokenError::NothingToClaim.into());
+    }
+    let (pda_trade_token_authority_address, bump_seed) =
+        vault_authority(program_id, state_account.key);
+    if get_associated_token_address(
+        &pda_trade_token_authority_address,
+        &account_state.trade_token_address,
+    ) != *pda_trade_token_ata_account.key
+    {
+        return Err(TokenError::WRONG_pda_trade_token_ata_account.into());
+    }
+
+    let amount = stake.rewards;
+    stake.rewards = 0;
+    account_state.staking_claimed += amount;
+    stake.serialize(&mut &mut stake_account.data.borrow_mut()[..])?;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    if staker_trade_token_ata_account.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                staker.key,
+                staker.key,
+                trade_token.key,
+                token_program.key,
+            ),
+            &[
+                trade_token.clone(),
+                staker_trade_token_ata_account.clone(),
+                staker.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            pda_trade_token_ata_account.key,
+            staker_trade_token_ata_account.key,
+            &pda_trade_token_authority_address,
+            &[],
+            amount,
+        )?,
+        &[
+            pda_trade_token_ata_account.clone(),
+            staker_trade_token_ata_account.clone(),
+            pda_trade_token_authority.clone(),
+        ],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    msg!("Staking rewards claimed {} {}", staker.key, amount);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+
+    #[tokio::test]
+    async fn stakers_earn_a_share_of_trade_fees() {
+        let mut args = create_args(stepped_linear());
+        args.staking_fee_bps = 5_000;
+        let mut launch = TestLaunch::start(args).await;
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 1_000_000,
+            })
+        };
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+        // Nothing staked yet, so the whole fee stays in `total`.
+        assert_eq!(launch.state().await.staking_rewards, 0);
+
+        let staker = launch.payer();
+        let (stake_account, _) = StakeAccount::address(&launch.program_id, &launch.state, &staker);
+        let (authority, _) = staking_authority(&launch.program_id, &launch.state);
+        let staking_vault = get_associated_token_address(&authority, &launch.mint);
+        let staked = launch.token_balance(launch.payer_tokens()).await / 2;
+        // Lamports sent to the stake account beforehand must not stop the stake.
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        launch
+            .send(
+                &[system_instruction::transfer(
+                    &staker,
+                    &stake_account,
+                    lamports,
+                )],
+                &[],
+            )
+            .await
+            .unwrap();
+        launch
+            .process(
+                SplMinterIntstruction::Stake(StakeArgs { amount: staked }),
+                vec![
+                    AccountMeta::new(staker, true),
+                    AccountMeta::new(launch.state, false),
+                    AccountMeta::new(stake_account, false),
+                    AccountMeta::new(launch.payer_tokens(), false),
+                    AccountMeta::new(staking_vault, false),
+                    AccountMeta::new_readonly(authority, false),
+                    AccountMeta::new_readonly(launch.mint, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new_readonly(spl_token::id(), false),
+                    AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(launch.token_balance(staking_vault).await, staked);
+        assert_eq!(launch.state().await.total_staked, staked);
+
+        let before = launch.state().await;
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+        let after = launch.state().await;
+        let fee = after.total - before.total + after.staking_rewards;
+        // Half the fee, down to what the accumulator can credit.
+        let increment = (fee / 2) as u128 * REWARD_PRECISION / staked as u128;
+        assert_eq!(after.reward_per_token, increment);
+        assert_eq!(
+            after.staking_rewards as u128,
+            increment * staked as u128 / REWARD_PRECISION
+        );
+
+        let claim_accounts = vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(launch.vault(), false),
+            AccountMeta::new(launch.payer_stable(), false),
+            AccountMeta::new_readonly(launch.vault_authority(), false),
+            AccountMeta::new_readonly(launch.stable_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let balance = launch.token_balance(launch.payer_stable()).await;
+        launch
+            .process(SplMinterIntstruction::ClaimRewards, claim_accounts.clone())
+            .await
+            .unwrap();
+        let paid = launch.token_balance(launch.payer_stable()).await - balance;
+        // The sole staker gets it all.
+        assert_eq!(paid, after.staking_rewards);
+        assert_eq!(launch.state().await.staking_claimed, paid);
+        let result = launch
+            .process(SplMinterIntstruction::ClaimRewards, claim_accounts)
+            .await;
+        assert_token_error(result, TokenError::NothingToClaim);
+
+        let unstake_accounts = vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new(launch.state, false),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(launch.payer_tokens(), false),
+            AccountMeta::new(staking_vault, false),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        let unstake = |amount| SplMinterIntstruction::Unstake(StakeArgs { amount });
+        let result = launch
+            .process(unstake(staked + 1), unstake_accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::ZeroStake);
+        launch
+            .process(unstake(staked), unstake_accounts)
+            .await
+            .unwrap();
+        assert_eq!(launch.token_balance(staking_vault).await, 0);
+        assert_eq!(launch.state().await.total_staked, 0);
+    }
+
+    #[test]
+    fn fee_shares_reach_a_realistic_stake() {
+        let mut state = State::try_from_slice(&[0; State::LEN]).unwrap();
+        state.staking_fee_bps = BPS_DENOMINATOR;
+        // A billion tokens of 7 decimals staked.
+        state.total_staked = 10_000_000_000_000_000;
+        let mut stake = StakeAccount {
+            amount: state.total_staked / 4,
+            ..StakeAccount::default()
+        };
+        assert_eq!(state.share_with_stakers(1_000), 1_000);
+        stake.settle(&state);
+        assert_eq!(stake.rewards, 250);
+
+        // What the accumulator can not credit stays with the claim authority.
+        state.total_staked = u64::MAX;
+        let mut stake = StakeAccount {
+            amount: u64::MAX,
+            reward_per_token_paid: state.reward_per_token,
+            ..StakeAccount::default()
+        };
+        let credited = state.share_with_stakers(1_000);
+        assert!(credited > 0 && credited < 1_000);
+        stake.settle(&state);
+        assert_eq!(stake.rewards, credited);
+        assert_eq!(state.staking_rewards, 1_000 + credited);
+    }
+}
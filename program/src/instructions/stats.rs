@@ -0,0 +1,336 @@
+use super::{
+    create::{create_pda_account, State},
+    trade::mul_div_u64,
+};
+use crate::{curve::TOKEN_UNIT, error::TokenError};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+/// Trading history of one wallet, stored at `[b"stats", state_account, wallet]`.
+/// Optional: only kept up to date by the trades that pass it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserStats {
+    pub state: Pubkey,
+    pub wallet: Pubkey,
+    /// Stable paid for buys, fees included.
+    pub stable_in: u64,
+    /// Stable received for sells, fees excluded.
+    pub stable_out: u64,
+    pub tokens_bought: u64,
+    pub tokens_sold: u64,
+    pub trade_count: u64,
+    /// Stable paid for the tokens still held. Sells remove their share at the
+    /// average cost.
+    pub cost_basis: u64,
+    /// Referrer named on the first tracked trade, default when none was.
+    /// Never changes afterwards.
+    pub referrer: Pubkey,
+}
+
+impl UserStats {
+    pub const LEN: usize = 32 + 32 + 8 * 6 + 32;
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"stats", state_account.as_ref(), wallet.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Reads the stats of `wallet`, creating them on its first tracked trade.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        wallet: &AccountInfo<'a>,
+        stats_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<UserStats, ProgramError> {
+        let (address, bump) = UserStats::address(program_id, state_account.key, wallet.key);
+        if address != *stats_account.key {
+            return Err(TokenError::WrongStatsAccount.into());
+        }
+        if stats_account.owner == program_id {
+            return UserStats::try_from_slice(&stats_account.data.borrow())
+                .map_err(|_| TokenError::WrongStatsAccount.into());
+        }
+        create_pda_account(
+            wallet,
+            stats_account,
+            UserStats::LEN,
+            program_id,
+            system_program,
+            &[
+                b"stats",
+                state_account.key.as_ref(),
+                wallet.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(UserStats {
+            state: *state_account.key,
+            wallet: *wallet.key,
+            ..UserStats::default()
+        })
+    }
+
+    /// Tokens bought and not sold yet.
+    pub fn position(&self) -> u64 {
+        self.tokens_bought.saturating_sub(self.tokens_sold)
+    }
+
+    /// Stable paid per whole token still held, 0 without a position.
+    pub fn average_cost(&self) -> u64 {
+        mul_div_u64(self.cost_basis, TOKEN_UNIT, self.position()).unwrap_or(0)
+    }
+
+    pub fn record_buy(&mut self, stable_in: u64, tokens: u64) {
+        self.stable_in += stable_in;
+        self.tokens_bought += tokens;
+        self.trade_count += 1;
+        self.cost_basis += stable_in;
+    }
+
+    pub fn record_sell(&mut self, tokens: u64, stable_out: u64) {
+        let position = self.position();
+        if position > 0 {
+            let sold_cost = mul_div_u64(self.cost_basis, tokens.min(position), position)
+                .unwrap_or(self.cost_basis);
+            self.cost_basis -= sold_cost;
+        }
+        self.stable_out += stable_out;
+        self.tokens_sold += tokens;
+        self.trade_count += 1;
+    }
+}
+
+/// Takes the stats account of `wallet` if it is the next account.
+pub fn next_user_stats<'a, 'b>(
+    program_id: &Pubkey,
+    state_account: &AccountInfo<'a>,
+    wallet: &AccountInfo<'a>,
+    accounts_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    system_program: &AccountInfo<'a>,
+) -> Result<Option<(&'b AccountInfo<'a>, UserStats)>, ProgramError> {
+    let (address, _) = UserStats::address(program_id, state_account.key, wallet.key);
+    let stats_account = match accounts_iter.as_slice().first() {
+        Some(account) if *account.key == address => next_account_info(accounts_iter)?,
+        _ => return Ok(None),
+    };
+    let stats = UserStats::load_or_create(
+        program_id,
+        state_account,
+        wallet,
+        stats_account,
+        system_program,
+    )?;
+    Ok(Some((stats_account, stats)))
+}
+
+pub fn save_user_stats(stats: Option<(&AccountInfo, UserStats)>) -> ProgramResult {
+    if let Some((stats_account, stats)) = stats {
+        stats.serialize(&mut &mut stats_account.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+impl State {
+    /// `new_buyer` when this is the first buy recorded in the buyer's
+    /// `UserStats`.
+    pub fn track_buy(&mut self, stable_in: u64, slot: u64, new_buyer: bool) {
+        self.buy_volume += stable_in;
+        self.buy_count += 1;
+        self.last_trade_slot = slot;
+        if new_buyer {
+            self.unique_buyers += 1;
+        }
+    }
+
+    pub fn track_sell(&mut self, stable_out: u64, slot: u64) {
+        self.sell_volume += stable_out;
+        self.sell_count += 1;
+        self.last_trade_slot = slot;
+    }
+
+    /// `round` is 0 for prices without one, see `ath_round`.
+    pub fn track_price(&mut self, price: u64, round: u64) {
+        if price > self.ath_price {
+            self.ath_price = price;
+            self.ath_round = round;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        curve::{CurveConfig, CurveKind},
+        instructions::trade::{
+            _currenRound, burnExactTokensForStableArgs, mintTokensForExactStableArgs,
+        },
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_program};
+    use spl_associated_token_account::instruction::create_associated_token_account;
+
+    #[tokio::test]
+    async fn trades_update_user_stats() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let (stats, _) = UserStats::address(&launch.program_id, &launch.state, &launch.payer());
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+        let mut buy_accounts = launch.mint_accounts();
+        buy_accounts.push(AccountMeta::new(stats, false));
+        let mut sell_accounts = launch.burn_accounts();
+        sell_accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        sell_accounts.push(AccountMeta::new(stats, false));
+
+        launch
+            .process(buy(500_000), buy_accounts.clone())
+            .await
+            .unwrap();
+        // Untracked trades leave the stats alone.
+        launch
+            .process(buy(500_000), launch.mint_accounts())
+            .await
+            .unwrap();
+        launch.process(buy(300_000), buy_accounts).await.unwrap();
+
+        let read = |data: Vec<u8>| UserStats::try_from_slice(&data).unwrap();
+        let before = read(launch.account(stats).await.data);
+        assert_eq!(before.wallet, launch.payer());
+        assert_eq!(before.stable_in, 800_000);
+        assert_eq!(before.trade_count, 2);
+        assert_eq!(before.cost_basis, 800_000);
+
+        let peak = launch.state().await;
+        let balance = launch.token_balance(launch.payer_stable()).await;
+        let sold = before.tokens_bought / 2;
+        launch
+            .process(
+                SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                    _inAmount: sold,
+                }),
+                sell_accounts,
+            )
+            .await
+            .unwrap();
+        let after = read(launch.account(stats).await.data);
+        assert_eq!(after.tokens_sold, sold);
+        assert_eq!(
+            after.stable_out,
+            launch.token_balance(launch.payer_stable()).await - balance
+        );
+        assert_eq!(after.trade_count, 3);
+        assert_eq!(after.cost_basis, 400_000);
+        assert_eq!(after.average_cost(), before.average_cost());
+
+        // The launch counts every trade, tracked or not.
+        let state = launch.state().await;
+        assert_eq!(state.buy_volume, 1_300_000);
+        assert_eq!(state.sell_volume, after.stable_out);
+        assert_eq!((state.buy_count, state.sell_count), (3, 1));
+        assert_eq!(state.unique_buyers, 1);
+        assert_eq!(
+            state.ath_price,
+            peak.curve.spot_price(None, peak.curve_supply).unwrap()
+        );
+        assert_eq!(state.ath_round, _currenRound(peak.curve_supply));
+    }
+
+    #[tokio::test]
+    async fn unique_buyers_ignore_token_account_history() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        let payer = launch.payer();
+        let tokens = launch.payer_tokens();
+        let (stats, _) = UserStats::address(&launch.program_id, &launch.state, &payer);
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 500_000,
+            })
+        };
+        let mut buy_accounts = launch.mint_accounts();
+        buy_accounts.push(AccountMeta::new(stats, false));
+
+        // The token account is opened before the first buy.
+        launch
+            .send(
+                &[create_associated_token_account(
+                    &payer,
+                    &payer,
+                    &launch.mint,
+                    &spl_token::id(),
+                )],
+                &[],
+            )
+            .await
+            .unwrap();
+        launch.process(buy(), buy_accounts.clone()).await.unwrap();
+        assert_eq!(launch.state().await.unique_buyers, 1);
+
+        // Emptying and closing it does not make the next buy a new buyer.
+        let balance = launch.token_balance(tokens).await;
+        launch
+            .send(
+                &[
+                    spl_token::instruction::burn(
+                        &spl_token::id(),
+                        &tokens,
+                        &launch.mint,
+                        &payer,
+                        &[],
+                        balance,
+                    )
+                    .unwrap(),
+                    spl_token::instruction::close_account(
+                        &spl_token::id(),
+                        &tokens,
+                        &payer,
+                        &payer,
+                        &[],
+                    )
+                    .unwrap(),
+                ],
+                &[],
+            )
+            .await
+            .unwrap();
+        launch.process(buy(), buy_accounts).await.unwrap();
+        let state = launch.state().await;
+        assert_eq!(state.buy_count, 2);
+        assert_eq!(state.unique_buyers, 1);
+    }
+
+    #[tokio::test]
+    async fn curves_without_rounds_keep_no_ath_round() {
+        let mut launch = TestLaunch::start(create_args(CurveConfig {
+            kind: CurveKind::ContinuousLinear,
+            param_a: 10,
+            param_b: 1,
+        }))
+        .await;
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 1_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        let state = launch.state().await;
+        assert!(state.ath_price > 10);
+        assert_eq!(state.ath_round, 0);
+    }
+}
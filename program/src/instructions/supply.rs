@@ -0,0 +1,126 @@
+use super::create::State;
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program::set_return_data,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
+    spl_token::state::Mint,
+};
+
+/// Returned by `ReconcileSupply` through the return data.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct SupplyReconciliation {
+    pub curve_supply: u64,
+    pub mint_supply: u64,
+    /// Tokens burned directly through the token program. They left the
+    /// mint supply but are still priced into the curve.
+    pub burned_outside_curve: u64,
+}
+
+pub fn reconcile_supply(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let claim_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+
+    if !claim_account.is_signer || *claim_account.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    if *mint_account.key != account_state.mint_account {
+        return Err(TokenError::WRONG_mint_account.into());
+    }
+
+    let mint = Mint::unpack(&mint_account.data.borrow())?;
+    let reconciliation = SupplyReconciliation {
+        curve_supply: account_state.curve_supply,
+        mint_supply: mint.supply,
+        burned_outside_curve: account_state.minted_supply().saturating_sub(mint.supply),
+    };
+    msg!(
+        "curve_supply: {}, mint_supply: {}, burned_outside_curve: {}",
+        reconciliation.curve_supply,
+        reconciliation.mint_supply,
+        reconciliation.burned_outside_curve
+    );
+    set_return_data(&reconciliation.try_to_vec()?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::mintTokensForExactStableArgs, processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    #[tokio::test]
+    async fn burns_outside_the_program_keep_the_price() {
+        let mut launch = TestLaunch::start(create_args(stepped_linear())).await;
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 1_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        let before = launch.state().await;
+        let price = before.curve.spot_price(None, before.curve_supply).unwrap();
+        let bought = launch.token_balance(launch.payer_tokens()).await;
+        let burned = bought / 3;
+        let payer = launch.payer();
+        launch
+            .send(
+                &[spl_token::instruction::burn(
+                    &spl_token::id(),
+                    &launch.payer_tokens(),
+                    &launch.mint,
+                    &payer,
+                    &[],
+                    burned,
+                )
+                .unwrap()],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let data = launch
+            .view(
+                SplMinterIntstruction::ReconcileSupply,
+                vec![
+                    AccountMeta::new_readonly(payer, true),
+                    AccountMeta::new_readonly(launch.state, false),
+                    AccountMeta::new_readonly(launch.mint, false),
+                ],
+            )
+            .await;
+        let reconciliation = SupplyReconciliation::try_from_slice(&data).unwrap();
+        assert_eq!(reconciliation.burned_outside_curve, burned);
+        assert_eq!(reconciliation.curve_supply, before.curve_supply);
+        assert_eq!(reconciliation.mint_supply, bought - burned);
+        let after = launch.state().await;
+        assert_eq!(after.curve_supply, before.curve_supply);
+        assert_eq!(
+            after.curve.spot_price(None, after.curve_supply).unwrap(),
+            price
+        );
+        assert_eq!(after.last_price, before.last_price);
+    }
+}
@@ -4,24 +4,37 @@ use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::Clock,
         entrypoint::ProgramResult,
         msg,
-        program::{invoke, invoke_signed},
-        program_pack::Pack,
+        program::{invoke, invoke_signed, set_return_data},
         pubkey::Pubkey,
+        sysvar::Sysvar,
     },
     spl_associated_token_account::{
         get_associated_token_address,
         instruction as associated_token_account_instruction,
     },
-    spl_token::{instruction as token_instruction, state::Mint},
+    spl_token::instruction as token_instruction,
 };
 construct_uint! {
     pub struct U128(2);
 }
 
+use super::{
+    allowlist::{AllowlistEntry, MintWithProofArgs},
+    candles::{next_candles, save_candles},
+    freeze::check_denylist,
+    graduate::{pool_buy, pool_sell, swap_stable_for_tokens, swap_tokens_for_stable},
+    oracle::{next_oracle, save_oracle},
+    presale::Contribution,
+    schedule::next_schedule,
+    referral::{credit_referral, next_trade_extras, save_referral},
+    stats::save_user_stats,
+    wallet::WalletRecord,
+};
 use crate::error::TokenError;
-use create::State;
+use create::{vault_authority, LaunchStatus, State};
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct mintTokensForExactStableArgs {
     pub _inAmount: u64,
@@ -37,7 +50,24 @@ pub struct burnExactTokensForStableArgs {
     pub _inAmount: u64,
 }
 
-const MAX_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000_000_0;
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct QuoteArgs {
+    pub is_buy: bool,
+    pub _inAmount: u64,
+}
+
+/// Returned by `Quote` through the return data.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct QuoteResult {
+    pub out_amount: u64,
+    pub fee: u64,
+    pub spot_price: u64,
+    /// Buy fee charged now, creator royalty included, in the units of
+    /// `FEE_PERCENT_POINT`.
+    pub fee_rate: u64,
+}
+
+pub(crate) const MAX_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000_000_0;
 const MAX_TOKEN_AMOUNT: u64 = 10_000_000_000_000;
 /////////////////////////////////////////////////
 ///  Main Constants, check before Deployment   //
@@ -45,11 +75,11 @@ const MAX_TOKEN_AMOUNT: u64 = 10_000_000_000_000;
 const START_PRICE: u64 = 1;
 const PRICE_INCREASE_STEP: u64 = 1;
 const INCREASE_FROM_ROUND: u64 = 1;
-const ROUND_VOLUME: u64 = 1_000_000 * u64::pow(10, 7);
+pub(crate) const ROUND_VOLUME: u64 = 1_000_000 * u64::pow(10, 7);
 /////////////////////////////////////////////////
 
-const FEE_PERCENT_POINT: u64 = 50000;
-const PERCENT_DENOMINATOR: u64 = 10000;
+pub(crate) const FEE_PERCENT_POINT: u64 = 50000;
+pub(crate) const PERCENT_DENOMINATOR: u64 = 10000;
 
 pub fn mintTokensForExactStableWithSlippage(
     program_id: &Pubkey,
@@ -59,31 +89,27 @@ pub fn mintTokensForExactStableWithSlippage(
     if args._inAmount > MAX_TOKEN_AMOUNT {
         return Err(TokenError::MAX_TOKEN_AMOUNT.into());
     }
-    let accounts_iter = &mut accounts.iter();
-
-    let mint_account = next_account_info(accounts_iter)?;
-    let mint = Mint::unpack(&mint_account.data.borrow()).unwrap();
-    let _distributedAmount = mint.supply;
-
-    let (out, _) = new_calcMintTokensForExactStable(_distributedAmount, args._inAmount);
-    //		assert!(out >= args._outNotLess,"Slippage occur");
-    if out < args._outNotLess {
-        return Err(TokenError::Slippage_occur.into());
-    }
-    mintTokensForExactStable(
-        program_id,
-        accounts,
-        mintTokensForExactStableArgs {
-            _inAmount: args._inAmount,
-        },
-    );
-    Ok(())
+    _mintTokensForExactStable(program_id, accounts, args._inAmount, args._outNotLess, None)
 }
 
 pub fn mintTokensForExactStable(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     args: mintTokensForExactStableArgs,
+) -> ProgramResult {
+    _mintTokensForExactStable(program_id, accounts, args._inAmount, 0, None)
+}
+
+/// `allowlist` is set for allowlist buys. Trailing accounts, each only when
+/// the launch needs it: allowlist entry, round schedule, presale contribution,
+/// wallet record, oracle, candles, denylist entry. Then, optionally, the
+/// buyer's `UserStats` and a referrer with its `Referral`.
+pub(crate) fn _mintTokensForExactStable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _inAmount: u64,
+    _outNotLess: u64,
+    allowlist: Option<&MintWithProofArgs>,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let mint_account = next_account_info(accounts_iter)?;
@@ -101,7 +127,10 @@ pub fn mintTokensForExactStable(
     //check  associated_token_account own payer, tradeToken ATA own payer
     //chack tradeToken ATA contains tradeToken token
     let (pda_trade_token_authority_address, bump_seed_3) =
-        Pubkey::find_program_address(&[b"ata_trade_token_authority"], &program_id);
+        vault_authority(program_id, state_account.key);
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
     let mut account_state = State::try_from_slice(&state_account.data.borrow()).unwrap();
 
     let rigth_pda_ata_trade_token_address = get_associated_token_address(
@@ -115,45 +144,216 @@ pub fn mintTokensForExactStable(
         return Err(TokenError::WRONG_mint_account.into());
     }
 
-    if args._inAmount > MAX_TOKEN_AMOUNT {
+    if _inAmount > MAX_TOKEN_AMOUNT {
         return Err(TokenError::MAX_TOKEN_AMOUNT.into());
     }
+    let now = Clock::get()?.unix_timestamp;
+    let mut allowlist_entry = None;
+    match allowlist {
+        Some(args) => {
+            let entry_account = next_account_info(accounts_iter)?;
+            let entry = AllowlistEntry::load_checked(
+                program_id,
+                state_account,
+                &account_state,
+                payer,
+                entry_account,
+                system_program,
+                now,
+                args,
+            )?;
+            if entry.used >= args.allocation {
+                return Err(TokenError::AllowlistAllocationExceeded.into());
+            }
+            allowlist_entry = Some((entry_account, entry, args.allocation));
+        }
+        None => account_state.check_buys_open(now)?,
+    }
+    if account_state.status == LaunchStatus::Graduated {
+        return swap_stable_for_tokens(program_id, accounts, _inAmount, _outNotLess);
+    }
+    let schedule = next_schedule(program_id, state_account, &account_state, accounts_iter)?;
+    if account_state.status == LaunchStatus::SaleCompleted {
+        return Err(TokenError::SaleCompleted.into());
+    }
+    account_state.settle_presale(now);
+    if account_state.status == LaunchStatus::Failed {
+        return Err(TokenError::PresaleFailed.into());
+    }
+    let presale = account_state.in_presale();
+    let mut buyAmount = _inAmount;
+    let mut contribution = None;
+    if presale {
+        let contribution_account = next_account_info(accounts_iter)?;
+        contribution = Some((
+            contribution_account,
+            Contribution::load_or_create(
+                program_id,
+                state_account,
+                payer,
+                contribution_account,
+                system_program,
+            )?,
+        ));
+        if account_state.hard_cap > 0 {
+            buyAmount = buyAmount.min(account_state.hard_cap - account_state.raised);
+        }
+    }
+    if let Some((_, entry, allocation)) = &allowlist_entry {
+        buyAmount = buyAmount.min(allocation - entry.used);
+    }
+    let mut wallet_record = None;
+    let mut wallet_tokens = u64::MAX;
+    if account_state.has_wallet_caps() {
+        let record_account = next_account_info(accounts_iter)?;
+        let record = WalletRecord::load_or_create(
+            program_id,
+            state_account,
+            payer,
+            record_account,
+            system_program,
+        )?;
+        let (tokens, stable) = account_state.wallet_room(&record)?;
+        wallet_tokens = tokens;
+        buyAmount = buyAmount.min(stable);
+        wallet_record = Some((record_account, record));
+    }
+    let oracle = next_oracle(program_id, state_account, &account_state, accounts_iter)?;
+    let candles = next_candles(program_id, state_account, &account_state, accounts_iter)?;
+    check_denylist(
+        program_id,
+        state_account,
+        &account_state,
+        payer,
+        accounts_iter,
+    )?;
+    let (mut stats, mut referral) = next_trade_extras(
+        program_id,
+        state_account,
+        payer,
+        accounts_iter,
+        Some(system_program),
+    )?;
     // 1. Calc distribution tokens
 
-    let mint = Mint::unpack(&mint_account.data.borrow()).unwrap();
-    let _distributedAmount = mint.supply;
+    let _distributedAmount = account_state.curve_supply;
     //	let _distributedAmount = 0;
     msg!(
-        "_distributedAmount: {}, args._inAmount: {} ",
+        "_distributedAmount: {}, _inAmount: {} ",
         _distributedAmount,
-        args._inAmount
+        _inAmount
     );
 
     //	let (outAmount, inAmountFee)= _calcMintTokensForExactStable(_distributedAmount,args._inAmount);
-    let (outAmount, inAmountFee) =
-        new_calcMintTokensForExactStable(_distributedAmount, args._inAmount);
+    let feeRate = account_state.buy_fee_rate(now) + account_state.creator_fee_rate();
+    let (mut outAmount, mut inAmountFee) =
+        account_state
+            .curve
+            .buy(schedule.as_ref(), _distributedAmount, buyAmount, feeRate)?;
+
+    // 2. Fill the last buyer up to max supply or their wallet cap, charging
+    // only what they get
+
+    let mut chargedAmount = buyAmount;
+    let mut sold_out = false;
+    let mut max_supply = account_state.curve.max_supply(schedule.as_ref())?;
+    if account_state.graduation_threshold > 0 {
+        max_supply = max_supply.min(account_state.graduation_threshold);
+    }
+    let supply_remaining = max_supply.saturating_sub(_distributedAmount);
+    let remaining = supply_remaining.min(wallet_tokens);
+    if outAmount >= remaining {
+        let (fillAmount, fillFee) = account_state.curve.stable_for_exact_tokens(
+            schedule.as_ref(),
+            _distributedAmount,
+            remaining,
+            feeRate,
+        )?;
+        outAmount = remaining;
+        chargedAmount = fillAmount.min(buyAmount);
+        inAmountFee = fillFee.min(chargedAmount);
+        sold_out = remaining == supply_remaining;
+    }
 
     msg!("outAmount: {}, inAmountFee: {} ", outAmount, inAmountFee);
     //	let (outAmount, inAmountFee)= _calcMintTokensForExactStable(0,args._inAmount);
     if outAmount == 0 {
         return Err(TokenError::ZeroBuy.into());
     }
+    if outAmount < _outNotLess {
+        return Err(TokenError::Slippage_occur.into());
+    }
+    account_state.check_price_impact(schedule.as_ref(), _distributedAmount, outAmount)?;
 
-    // 2. Charge Fee
+    // 3. Charge Fee and mint distribution token
 
-    account_state.total += inAmountFee;
+    // Referrers get no share of presale fees, which refunds hand back.
+    let creator_fee = account_state.creator_buy_fee(inAmountFee, feeRate);
+    let protocol_fee = inAmountFee - creator_fee;
+    let referral_fee = if presale {
+        0
+    } else {
+        credit_referral(&mut referral, &account_state, protocol_fee)
+    };
+    let staker_fee = if presale {
+        0
+    } else {
+        account_state.share_with_stakers(protocol_fee - referral_fee)
+    };
+    account_state.total += protocol_fee - referral_fee - staker_fee;
+    account_state.creator_fees += creator_fee;
+    account_state.reserve += chargedAmount - inAmountFee;
+    account_state.curve_supply += outAmount;
+    let first_buy = matches!(&stats, Some((_, stats)) if stats.tokens_bought == 0);
+    account_state.track_buy(chargedAmount, Clock::get()?.slot, first_buy);
+    let last_price = account_state.last_price;
+    if let Ok(price) = account_state
+        .curve
+        .spot_price(schedule.as_ref(), account_state.curve_supply)
+    {
+        let round = account_state
+            .curve
+            .round(schedule.as_ref(), account_state.curve_supply)?;
+        account_state.track_price(price, round);
+        account_state.update_price(now, price);
+    }
+    if let Some((contribution_account, mut contribution)) = contribution {
+        account_state.raised += chargedAmount;
+        if account_state.hard_cap > 0
+            && account_state.raised >= account_state.hard_cap
+            && account_state.status == LaunchStatus::Active
+        {
+            account_state.status = LaunchStatus::Succeeded;
+            msg!("Presale reached hard cap: {}", account_state.raised);
+        }
+        contribution.contributed += chargedAmount;
+        contribution.fees += inAmountFee;
+        contribution.tokens += outAmount;
+        contribution.serialize(&mut &mut contribution_account.data.borrow_mut()[..])?;
+    }
+    if sold_out {
+        account_state.complete_sale(max_supply);
+    }
+    if let Some((record_account, mut record)) = wallet_record {
+        record.bought_tokens += outAmount;
+        record.spent_stable += chargedAmount;
+        record.serialize(&mut &mut record_account.data.borrow_mut()[..])?;
+    }
+    if let Some((entry_account, mut entry, _)) = allowlist_entry {
+        entry.used += chargedAmount;
+        entry.serialize(&mut &mut entry_account.data.borrow_mut()[..])?;
+    }
+    if let Some((_, stats)) = &mut stats {
+        stats.record_buy(chargedAmount, outAmount);
+    }
+    save_user_stats(stats)?;
+    save_referral(referral)?;
+    save_oracle(oracle, &account_state, now)?;
+    save_candles(candles, &account_state, now, last_price, chargedAmount)?;
     account_state
         .serialize(&mut &mut state_account.data.borrow_mut()[..])
         .unwrap();
 
-    // 3. Mint distribution token
-    // 3.0 check max supply
-
-    let current_supply = mint.supply;
-    if (outAmount + current_supply) > MAX_TOTAL_SUPPLY {
-        return Err(TokenError::MAX_TOTAL_SUPPLY.into());
-    }
-
     //3.1. chek if ata exist
     let (_, bump_seed_2) = Pubkey::find_program_address(&[b"mint_authority"], &program_id);
     if associated_token_account.lamports() == 0 {
@@ -196,13 +396,6 @@ pub fn mintTokensForExactStable(
     )?;
     msg!("Mint on acc.: {}", associated_token_account.key);
 
-    let mint_01 = Mint::unpack(&mint_account.data.borrow()).unwrap();
-    let _distributedAmount_01 = mint.supply;
-    /* 	msg!(
-           "Supply after mint: {} ",
-           _distributedAmount_01
-       );
-    */
     ////	_mintFor(msg.sender, outAmount);
 
     // 4. Get payment
@@ -216,7 +409,7 @@ pub fn mintTokensForExactStable(
             pda_trade_token_ata_account.key,
             payer.key,
             &[payer.key],
-            args._inAmount,
+            chargedAmount,
         )?,
         &[
             payer_trade_token_ata_account.clone(),
@@ -229,17 +422,22 @@ pub fn mintTokensForExactStable(
     //	tradeToken.safeTransferFrom(msg.sender, address(this), _inAmount);
 
     //	emit Deal(msg.sender, address(tradeToken), _inAmount, outAmount);
-    /* 	msg!(
-            "Deal {} {} {} {}",
-            payer.key,
-            payer_trade_token_ata_account.key,
-            args._inAmount,
-            outAmount
-        );
-    */
+    msg!(
+        "Deal {} {} {} {} fee: {} fee_rate: {} creator_fee: {}",
+        payer.key,
+        payer_trade_token_ata_account.key,
+        chargedAmount,
+        outAmount,
+        inAmountFee,
+        feeRate,
+        creator_fee
+    );
     Ok(())
 }
 
+/// Trailing accounts: the round schedule for `CurveKind::Schedule`, the oracle,
+/// the candles and the seller's denylist entry once initialized. Then, optionally, the system program
+/// followed by the seller's `UserStats` and a referrer with its `Referral`.
 pub fn burnExactTokensForStable(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -260,7 +458,10 @@ pub fn burnExactTokensForStable(
     //		let associated_token_program = next_account_info(accounts_iter)?;
 
     let (pda_trade_token_authority_address, bump_seed_3) =
-        Pubkey::find_program_address(&[b"ata_trade_token_authority"], &program_id);
+        vault_authority(program_id, state_account.key);
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
     let mut account_state = State::try_from_slice(&state_account.data.borrow()).unwrap();
 
     let rigth_pda_ata_trade_token_address = get_associated_token_address(
@@ -273,22 +474,66 @@ pub fn burnExactTokensForStable(
     if *mint_account.key != account_state.mint_account {
         return Err(TokenError::WRONG_mint_account.into());
     }
+    let now = Clock::get()?.unix_timestamp;
+    account_state.check_sells_open(now)?;
+    if account_state.status == LaunchStatus::Graduated {
+        return swap_tokens_for_stable(program_id, accounts, args._inAmount);
+    }
+    let schedule = next_schedule(program_id, state_account, &account_state, accounts_iter)?;
+    account_state.settle_presale(now);
+    account_state.check_presale_settled()?;
+    let oracle = next_oracle(program_id, state_account, &account_state, accounts_iter)?;
+    let candles = next_candles(program_id, state_account, &account_state, accounts_iter)?;
+    check_denylist(
+        program_id,
+        state_account,
+        &account_state,
+        payer,
+        accounts_iter,
+    )?;
+    let (mut stats, mut referral) =
+        next_trade_extras(program_id, state_account, payer, accounts_iter, None)?;
 
     // 1. Calc distribution tokens
-    let mint = Mint::unpack(&mint_account.data.borrow()).unwrap();
-    let _distributedAmount = mint.supply;
+    let _distributedAmount = account_state.curve_supply;
 
-    let (outAmount, outAmountFee) =
-        new_calcBurnExactTokensForStable(_distributedAmount, args._inAmount);
+    let (sellAmount, outAmountFee) =
+        account_state
+            .curve
+            .sell(schedule.as_ref(), _distributedAmount, args._inAmount)?;
     //		assert!(outAmount > 0,"Cant buy zero");
-    if outAmount == 0 {
+    if sellAmount == 0 {
         return Err(TokenError::ZeroBuy.into());
     }
+    let (outAmount, creator_fee) = account_state.take_creator_fee(sellAmount);
     // 2. Charge Fee
-    account_state.total += outAmountFee;
+    let referral_fee = credit_referral(&mut referral, &account_state, outAmountFee);
+    let staker_fee = account_state.share_with_stakers(outAmountFee - referral_fee);
+    account_state.total += outAmountFee - referral_fee - staker_fee;
+    account_state.creator_fees += creator_fee;
+    account_state.reserve = account_state
+        .reserve
+        .checked_sub(sellAmount + outAmountFee)
+        .ok_or(TokenError::ReserveExceeded)?;
+    account_state.curve_supply -= args._inAmount;
+    account_state.track_sell(outAmount, Clock::get()?.slot);
+    let last_price = account_state.last_price;
+    if let Ok(price) = account_state
+        .curve
+        .spot_price(schedule.as_ref(), account_state.curve_supply)
+    {
+        account_state.update_price(now, price);
+    }
     account_state
         .serialize(&mut &mut state_account.data.borrow_mut()[..])
         .unwrap();
+    if let Some((_, stats)) = &mut stats {
+        stats.record_sell(args._inAmount, outAmount);
+    }
+    save_user_stats(stats)?;
+    save_referral(referral)?;
+    save_oracle(oracle, &account_state, now)?;
+    save_candles(candles, &account_state, now, last_price, outAmount)?;
 
     //		fee.total += outAmountFee;
 
@@ -313,7 +558,7 @@ pub fn burnExactTokensForStable(
     // 4. Get payment
 
     let (pda_trade_token_ata_authority, bump_seed_2) =
-        Pubkey::find_program_address(&[b"ata_trade_token_authority"], &program_id);
+        vault_authority(program_id, state_account.key);
 
     invoke_signed(
         &token_instruction::transfer(
@@ -331,24 +576,75 @@ pub fn burnExactTokensForStable(
             //				payer.clone(),
             //				token_program.clone(),
         ],
-        &[&[b"ata_trade_token_authority", &[bump_seed_2]]],
+        &[&[b"vault", state_account.key.as_ref(), &[bump_seed_2]]],
     )?;
 
     //        tradeToken.safeTransfer(msg.sender, outAmount);
 
     //        emit Deal(msg.sender, address(this), _inAmount, outAmount);
     msg!(
-        "Deal {} {} {} {}",
+        "Deal {} {} {} {} creator_fee: {}",
         payer.key,
         pda_trade_token_ata_account.key,
         args._inAmount,
-        outAmount
+        outAmount,
+        creator_fee
     );
 
     Ok(())
 }
 
-fn mul_div_u64(a: u64, b: u64, divisor: u64) -> Option<u64> {
+pub fn quote(program_id: &Pubkey, accounts: &[AccountInfo], args: QuoteArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let state_account = next_account_info(accounts_iter)?;
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let account_state = State::try_from_slice(&state_account.data.borrow())?;
+    if account_state.status == LaunchStatus::Graduated {
+        let (out_amount, fee) = if args.is_buy {
+            pool_buy(&account_state, args._inAmount)?
+        } else {
+            let (out_amount, fee) = pool_sell(&account_state, args._inAmount)?;
+            let (out_amount, creator_fee) = account_state.take_creator_fee(out_amount);
+            (out_amount, fee + creator_fee)
+        };
+        let result = QuoteResult {
+            out_amount,
+            fee,
+            spot_price: account_state
+                .pool_price()
+                .ok_or(TokenError::MathOverflow)?,
+            fee_rate: account_state.pool_fee_rate(),
+        };
+        set_return_data(&result.try_to_vec()?);
+        return Ok(());
+    }
+    let schedule = next_schedule(program_id, state_account, &account_state, accounts_iter)?;
+    let _distributedAmount = account_state.curve_supply;
+
+    let curve = account_state.curve;
+    let fee_rate = account_state.buy_fee_rate(Clock::get()?.unix_timestamp)
+        + account_state.creator_fee_rate();
+    let (out_amount, fee) = if args.is_buy {
+        curve.buy(schedule.as_ref(), _distributedAmount, args._inAmount, fee_rate)?
+    } else {
+        let (out_amount, fee) =
+            curve.sell(schedule.as_ref(), _distributedAmount, args._inAmount)?;
+        let (out_amount, creator_fee) = account_state.take_creator_fee(out_amount);
+        (out_amount, fee + creator_fee)
+    };
+    let result = QuoteResult {
+        out_amount,
+        fee,
+        spot_price: curve.spot_price(schedule.as_ref(), _distributedAmount)?,
+        fee_rate,
+    };
+    set_return_data(&result.try_to_vec()?);
+    Ok(())
+}
+
+pub(crate) fn mul_div_u64(a: u64, b: u64, divisor: u64) -> Option<u64> {
     let result = U128::from(a)
         .checked_mul(b.into())?
         .checked_div(divisor.into())?;
@@ -359,9 +655,19 @@ fn mul_div_u64(a: u64, b: u64, divisor: u64) -> Option<u64> {
     }
 }
 
-fn new_calcMintTokensForExactStable(_distributedAmount: u64, _inAmount: u64) -> (u64, u64) {
+pub(crate) fn new_calcMintTokensForExactStable(_distributedAmount: u64, _inAmount: u64) -> (u64, u64) {
+    new_calcMintTokensForExactStableWithFee(_distributedAmount, _inAmount, FEE_PERCENT_POINT)
+}
+
+/// `new_calcMintTokensForExactStable` charging `_feePercentPoint` instead of
+/// `FEE_PERCENT_POINT`.
+pub(crate) fn new_calcMintTokensForExactStableWithFee(
+    _distributedAmount: u64,
+    _inAmount: u64,
+    _feePercentPoint: u64,
+) -> (u64, u64) {
     let mut inA128: u128 = _inAmount as u128 * 100 * PERCENT_DENOMINATOR as u128
-        / (100 * PERCENT_DENOMINATOR as u128 + FEE_PERCENT_POINT as u128);
+        / (100 * PERCENT_DENOMINATOR as u128 + _feePercentPoint as u128);
     let mut inCleanedUSDTAmount = u64::try_from(inA128).unwrap();
 
     //	let mut inA = mul_div_u64(_inAmount,100 * PERCENT_DENOMINATOR,(100 * PERCENT_DENOMINATOR  + FEE_PERCENT_POINT )).unwrap();
@@ -430,7 +736,7 @@ fn new_calcMintTokensForExactStable(_distributedAmount: u64, _inAmount: u64) ->
     (memcoinAmount, inAmountFee)
 }
 
-fn new_calcBurnExactTokensForStable(_distributedAmount: u64, _inAmount: u64) -> (u64, u64) {
+pub(crate) fn new_calcBurnExactTokensForStable(_distributedAmount: u64, _inAmount: u64) -> (u64, u64) {
     let mut bAm = _inAmount;
     let dstTokenDecimals = 7;
 
@@ -516,7 +822,7 @@ fn _priceInUnitsAndRemainByRound(_distributedAmount: u64, _round: u64) -> (u64,
     (price, rest)
 }
 
-fn _priceForRound(_round: u64) -> u64 {
+pub(crate) fn _priceForRound(_round: u64) -> u64 {
     let price = if _round < INCREASE_FROM_ROUND {
         START_PRICE
     } else {
@@ -525,7 +831,7 @@ fn _priceForRound(_round: u64) -> u64 {
     price
 }
 
-fn _currenRound(_distributedAmount: u64) -> u64 {
+pub(crate) fn _currenRound(_distributedAmount: u64) -> u64 {
     return _distributedAmount / ROUND_VOLUME + 1;
 }
 
@@ -0,0 +1,223 @@
+use super::{
+    create::State,
+    trade::{FEE_PERCENT_POINT, PERCENT_DENOMINATOR},
+};
+use crate::error::TokenError;
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RescheduleArgs {
+    pub trading_starts_at: i64,
+    pub sells_enabled_at: i64,
+}
+
+/// `sells_enabled_at` may not open sells before buys. 0 opens them with buys.
+pub fn validate_trading_window(trading_starts_at: i64, sells_enabled_at: i64) -> ProgramResult {
+    if sells_enabled_at != 0 && sells_enabled_at < trading_starts_at {
+        return Err(TokenError::InvalidTradingWindow.into());
+    }
+    Ok(())
+}
+
+/// A launch fee must start at or above the normal fee and decay over a
+/// positive period.
+pub fn validate_fee_decay(launch_fee_rate: u64, fee_decay_period: i64) -> ProgramResult {
+    if launch_fee_rate != 0
+        && (!(FEE_PERCENT_POINT..=100 * PERCENT_DENOMINATOR).contains(&launch_fee_rate)
+            || fee_decay_period <= 0)
+    {
+        return Err(TokenError::InvalidFeeDecay.into());
+    }
+    Ok(())
+}
+
+impl State {
+    /// Buy fee at `now`, falling linearly from `launch_fee_rate` at
+    /// `trading_starts_at` to `FEE_PERCENT_POINT` after `fee_decay_period`.
+    /// Allowlist buys before the start pay the normal fee.
+    pub fn buy_fee_rate(&self, now: i64) -> u64 {
+        if self.launch_fee_rate == 0 || now < self.trading_starts_at {
+            return FEE_PERCENT_POINT;
+        }
+        let elapsed = (now - self.trading_starts_at) as u128;
+        let period = self.fee_decay_period as u128;
+        if elapsed >= period {
+            return FEE_PERCENT_POINT;
+        }
+        let extra = (self.launch_fee_rate - FEE_PERCENT_POINT) as u128;
+        FEE_PERCENT_POINT + (extra * (period - elapsed) / period) as u64
+    }
+
+    pub fn check_buys_open(&self, now: i64) -> ProgramResult {
+        if now < self.trading_starts_at {
+            return Err(TokenError::TradingNotStarted.into());
+        }
+        Ok(())
+    }
+
+    pub fn check_sells_open(&self, now: i64) -> ProgramResult {
+        self.check_buys_open(now)?;
+        if now < self.sells_enabled_at {
+            return Err(TokenError::SellsNotEnabled.into());
+        }
+        Ok(())
+    }
+}
+
+/// Moves the trading window of a launch that has not started yet.
+pub fn reschedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RescheduleArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let claim_account = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut account_state = State::try_from_slice(&state_account.data.borrow())?;
+
+    if !claim_account.is_signer || *claim_account.key != account_state.claim_authority {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now >= account_state.trading_starts_at {
+        return Err(TokenError::TradingStarted.into());
+    }
+    validate_trading_window(args.trading_starts_at, args.sells_enabled_at)?;
+
+    account_state.trading_starts_at = args.trading_starts_at;
+    account_state.sells_enabled_at = args.sells_enabled_at;
+    account_state.serialize(&mut &mut state_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Rescheduled trading_starts_at: {}, sells_enabled_at: {}",
+        args.trading_starts_at,
+        args.sells_enabled_at
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs},
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    #[tokio::test]
+    async fn trades_wait_for_their_window() {
+        // Far after the test clock, reached with `warp_to`.
+        let starts_at = i64::MAX / 4;
+        let mut args = create_args(stepped_linear());
+        args.trading_starts_at = starts_at;
+        let mut launch = TestLaunch::start(args).await;
+        let buy = || {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: 1_000_000,
+            })
+        };
+        let sell = |amount| {
+            SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                _inAmount: amount,
+            })
+        };
+        let reschedule_accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new(launch.state, false),
+        ];
+        let reschedule = |trading_starts_at, sells_enabled_at| {
+            SplMinterIntstruction::Reschedule(RescheduleArgs {
+                trading_starts_at,
+                sells_enabled_at,
+            })
+        };
+
+        let result = launch.process(buy(), launch.mint_accounts()).await;
+        assert_token_error(result, TokenError::TradingNotStarted);
+        let result = launch
+            .process(
+                reschedule(starts_at, starts_at - 1),
+                reschedule_accounts.clone(),
+            )
+            .await;
+        assert_token_error(result, TokenError::InvalidTradingWindow);
+        launch
+            .process(
+                reschedule(starts_at * 2, starts_at * 3),
+                reschedule_accounts.clone(),
+            )
+            .await
+            .unwrap();
+
+        launch.warp_to(starts_at * 2).await;
+        launch.process(buy(), launch.mint_accounts()).await.unwrap();
+        let result = launch
+            .process(reschedule(starts_at * 4, 0), reschedule_accounts)
+            .await;
+        assert_token_error(result, TokenError::TradingStarted);
+        let tokens = launch.token_balance(launch.payer_tokens()).await;
+        let result = launch.process(sell(tokens), launch.burn_accounts()).await;
+        assert_token_error(result, TokenError::SellsNotEnabled);
+
+        launch.warp_to(starts_at * 3).await;
+        launch
+            .process(sell(tokens), launch.burn_accounts())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn launch_fee_decays_to_normal_fee() {
+        assert!(validate_fee_decay(FEE_PERCENT_POINT - 1, 1_000).is_err());
+        assert!(validate_fee_decay(50 * PERCENT_DENOMINATOR, 0).is_err());
+
+        let starts_at = i64::MAX / 4;
+        let mut args = create_args(stepped_linear());
+        args.trading_starts_at = starts_at;
+        args.launch_fee_rate = 50 * PERCENT_DENOMINATOR;
+        args.fee_decay_period = 1_000;
+        let mut launch = TestLaunch::start(args).await;
+        let buy = |amount| {
+            SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                _inAmount: amount,
+            })
+        };
+
+        // 50% at the start, 27.5% halfway through, then the normal 5%.
+        launch.warp_to(starts_at).await;
+        launch
+            .process(buy(1_500_000), launch.mint_accounts())
+            .await
+            .unwrap();
+        assert_eq!(launch.state().await.total, 500_000);
+        launch.warp_to(starts_at + 500).await;
+        launch
+            .process(buy(1_275_000), launch.mint_accounts())
+            .await
+            .unwrap();
+        assert_eq!(launch.state().await.total, 775_000);
+        launch.warp_to(starts_at + 1_000).await;
+        launch
+            .process(buy(1_050_000), launch.mint_accounts())
+            .await
+            .unwrap();
+        assert_eq!(launch.state().await.total, 825_000);
+    }
+}
@@ -0,0 +1,416 @@
+use super::create::{create_pda_account, CreateTokenArgs, State};
+use crate::{curve::CurveKind, error::TokenError};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction as associated_token_account_instruction,
+    },
+    spl_token::instruction as token_instruction,
+};
+
+/// Pre-allocation of a launch, stored at `[b"vesting", state]`. The tokens sit
+/// in the associated token account of this PDA until the beneficiary claims
+/// them.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vesting {
+    pub state: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+    pub start: i64,
+    /// Nothing vests before the cliff. At the cliff the part vested linearly
+    /// since `start` unlocks at once.
+    pub cliff_end: i64,
+    pub end: i64,
+}
+
+impl Vesting {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 * 3;
+
+    pub fn address(program_id: &Pubkey, state: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vesting", state.as_ref()], program_id)
+    }
+
+    pub fn load(
+        program_id: &Pubkey,
+        state_account: &AccountInfo,
+        vesting_account: &AccountInfo,
+    ) -> Result<Vesting, ProgramError> {
+        let (address, _) = Vesting::address(program_id, state_account.key);
+        if address != *vesting_account.key || vesting_account.owner != program_id {
+            return Err(TokenError::WrongVestingAccount.into());
+        }
+        Vesting::try_from_slice(&vesting_account.data.borrow())
+            .map_err(|_| TokenError::WrongVestingAccount.into())
+    }
+
+    /// Tokens vested at `now`, claimed ones included.
+    pub fn vested(&self, now: i64) -> u64 {
+        if now < self.cliff_end {
+            return 0;
+        }
+        if now >= self.end {
+            return self.amount;
+        }
+        (self.amount as u128 * (now - self.start) as u128 / (self.end - self.start) as u128) as u64
+    }
+}
+
+pub fn validate_vesting(args: &CreateTokenArgs) -> ProgramResult {
+    if args.vesting_amount == 0 {
+        return Ok(());
+    }
+    if args.vesting_beneficiary == Pubkey::default()
+        || args.vesting_cliff < 0
+        || args.vesting_duration <= 0
+        || args.vesting_cliff > args.vesting_duration
+    {
+        return Err(TokenError::InvalidVesting.into());
+    }
+    // A schedule curve gets its rounds after creation, with no supply sold.
+    if args.curve.kind == CurveKind::Schedule
+        || args.vesting_amount > args.curve.max_supply(None)?
+    {
+        return Err(TokenError::InvalidVesting.into());
+    }
+    Ok(())
+}
+
+/// Creates the vesting account and its escrow, then mints the pre-allocation
+/// into the escrow. Called by `Create` once the mint exists.
+#[allow(clippy::too_many_arguments)]
+pub fn create_vesting<'a>(
+    program_id: &Pubkey,
+    vesting: &Vesting,
+    state_account: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    mint_authority: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    vesting_account: &AccountInfo<'a>,
+    escrow_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    associated_token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (address, bump) = Vesting::address(program_id, state_account.key);
+    if address != *vesting_account.key {
+        return Err(TokenError::WrongVestingAccount.into());
+    }
+    if get_associated_token_address(&address, mint_account.key) != *escrow_account.key {
+        return Err(TokenError::WrongVestingAccount.into());
+    }
+    create_pda_account(
+        payer,
+        vesting_account,
+        Vesting::LEN,
+        program_id,
+        system_program,
+        &[b"vesting", state_account.key.as_ref(), &[bump]],
+    )?;
+    invoke(
+        &associated_token_account_instruction::create_associated_token_account(
+            payer.key,
+            &address,
+            mint_account.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            escrow_account.clone(),
+            vesting_account.clone(),
+            mint_account.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+    let (mint_authority_address, mint_bump) =
+        Pubkey::find_program_address(&[b"mint_authority"], program_id);
+    invoke_signed(
+        &token_instruction::mint_to(
+            token_program.key,
+            mint_account.key,
+            escrow_account.key,
+            &mint_authority_address,
+            &[],
+            vesting.amount,
+        )?,
+        &[
+            mint_account.clone(),
+            escrow_account.clone(),
+            mint_authority.clone(),
+        ],
+        &[&[b"mint_authority", &[mint_bump]]],
+    )?;
+
+    vesting.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+    msg!(
+        "Vesting {} for {}: {}",
+        vesting_account.key,
+        vesting.beneficiary,
+        vesting.amount
+    );
+    Ok(())
+}
+
+/// Pays the beneficiary what vested so far. Accounts: beneficiary, state,
+/// vesting, escrow, beneficiary token account, mint, system program, token
+/// program, associated token program.
+pub fn claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let beneficiary = next_account_info(accounts_iter)?;
+    let state_account = next_account_info(accounts_iter)?;
+    let vesting_account = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let beneficiary_token_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    if state_account.owner != program_id {
+        return Err(TokenError::WrongStateAccount.into());
+    }
+    let mut vesting = Vesting::load(program_id, state_account, vesting_account)?;
+    if !beneficiary.is_signer || *beneficiary.key != vesting.beneficiary {
+        return Err(TokenError::Unauthorized.into());
+    }
+    let (address, bump) = Vesting::address(program_id, state_account.key);
+    if get_associated_token_address(&address, mint_account.key) != *escrow_account.key {
+        return Err(TokenError::WrongVestingAccount.into());
+    }
+    let amount = vesting.vested(Clock::get()?.unix_timestamp) - vesting.claimed;
+    if amount == 0 {
+        return Err(TokenError::NothingToClaim.into());
+    }
+    vesting.claimed += amount;
+    vesting.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+
+    if beneficiary_token_account.lamports() == 0 {
+        invoke(
+            &associated_token_account_instruction::create_associated_token_account(
+                beneficiary.key,
+                beneficiary.key,
+                mint_account.key,
+                token_program.key,
+            ),
+            &[
+                mint_account.clone(),
+                beneficiary_token_account.clone(),
+                beneficiary.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            escrow_account.key,
+            beneficiary_token_account.key,
+            &address,
+            &[],
+            amount,
+        )?,
+        &[
+            escrow_account.clone(),
+            beneficiary_token_account.clone(),
+            vesting_account.clone(),
+        ],
+        &[&[b"vesting", state_account.key.as_ref(), &[bump]]],
+    )?;
+
+    msg!(
+        "Vested claimed {} {}, left: {}",
+        beneficiary.key,
+        amount,
+        vesting.amount - vesting.claimed
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::trade::{burnExactTokensForStableArgs, mintTokensForExactStableArgs},
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::{instruction::AccountMeta, system_instruction, system_program};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    const PREALLOCATION: u64 = 1_000_000_000;
+
+    #[tokio::test]
+    async fn preallocation_vests_after_the_cliff() {
+        let mut launch = TestLaunch::new().await;
+        let mut args = create_args(stepped_linear());
+        args.vesting_amount = PREALLOCATION;
+        args.vesting_beneficiary = launch.payer();
+        args.vesting_cliff = 100;
+        args.vesting_duration = 1_000;
+        let mut invalid = create_args(stepped_linear());
+        invalid.vesting_amount = PREALLOCATION;
+        invalid.vesting_beneficiary = launch.payer();
+        invalid.vesting_cliff = 2_000;
+        invalid.vesting_duration = 1_000;
+        assert!(validate_vesting(&invalid).is_err());
+        let start = launch.now().await;
+        let stable = launch.token_balance(launch.payer_stable()).await;
+        launch.create(args).await;
+
+        let (vesting, _) = Vesting::address(&launch.program_id, &launch.state);
+        let escrow = get_associated_token_address(&vesting, &launch.mint);
+        assert_eq!(launch.token_balance(escrow).await, PREALLOCATION);
+        let state = launch.state().await;
+        assert_eq!(state.curve_supply, PREALLOCATION);
+        // The payer backs the pre-allocation as if buying it.
+        let (backing, _) = state
+            .curve
+            .stable_for_exact_tokens(None, 0, PREALLOCATION, 0)
+            .unwrap();
+        assert_eq!(state.reserve, backing);
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            stable - backing
+        );
+        assert_eq!(launch.token_balance(launch.vault()).await, backing);
+        assert_eq!(
+            state.last_price,
+            state.curve.spot_price(None, PREALLOCATION).unwrap()
+        );
+        assert_eq!(launch.mint_supply().await, state.minted_supply());
+
+        let accounts = vec![
+            AccountMeta::new(launch.payer(), true),
+            AccountMeta::new_readonly(launch.state, false),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(launch.payer_tokens(), false),
+            AccountMeta::new_readonly(launch.mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let result = launch
+            .process(SplMinterIntstruction::ClaimVested, accounts.clone())
+            .await;
+        assert_token_error(result, TokenError::NothingToClaim);
+
+        launch.warp_to(start + 500).await;
+        launch
+            .process(SplMinterIntstruction::ClaimVested, accounts.clone())
+            .await
+            .unwrap();
+        let claimed = launch.token_balance(launch.payer_tokens()).await;
+        assert!((PREALLOCATION / 2..PREALLOCATION).contains(&claimed));
+
+        launch
+            .process(
+                SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+                    _inAmount: 1_000_000,
+                }),
+                launch.mint_accounts(),
+            )
+            .await
+            .unwrap();
+        let bought = launch.state().await.curve_supply - PREALLOCATION;
+        let sell = |amount| {
+            SplMinterIntstruction::BurnExactTokensForStable(burnExactTokensForStableArgs {
+                _inAmount: amount,
+            })
+        };
+        // Claimed tokens sell against their own backing, the bought ones can
+        // still all be sold after them.
+        launch
+            .process(sell(claimed), launch.burn_accounts())
+            .await
+            .unwrap();
+        launch
+            .process(sell(bought), launch.burn_accounts())
+            .await
+            .unwrap();
+        let state = launch.state().await;
+        assert_eq!(state.curve_supply, PREALLOCATION - claimed);
+        assert_eq!(
+            launch.token_balance(launch.vault()).await,
+            state.reserve + state.total
+        );
+        assert!(state.reserve > 0);
+
+        launch.warp_to(start + 1_000).await;
+        launch
+            .process(SplMinterIntstruction::ClaimVested, accounts)
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(launch.payer_tokens()).await,
+            PREALLOCATION - claimed
+        );
+    }
+
+    #[tokio::test]
+    async fn prefunded_vesting_account_does_not_block_create() {
+        let mut launch = TestLaunch::new().await;
+        let mut args = create_args(stepped_linear());
+        args.vesting_amount = PREALLOCATION;
+        args.vesting_beneficiary = launch.payer();
+        args.vesting_duration = 1_000;
+        args.claime_authority = launch.payer();
+        let mint = Keypair::new();
+        let state = Keypair::new();
+        launch.mint = mint.pubkey();
+        launch.state = state.pubkey();
+
+        let (vesting, _) = Vesting::address(&launch.program_id, &launch.state);
+        let lamports = launch
+            .context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(0);
+        launch
+            .send(
+                &[system_instruction::transfer(
+                    &launch.payer(),
+                    &vesting,
+                    lamports,
+                )],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let mut accounts = launch.create_accounts().await;
+        accounts.push(AccountMeta::new(vesting, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(&vesting, &launch.mint),
+            false,
+        ));
+        accounts.push(AccountMeta::new(launch.payer_stable(), false));
+        launch
+            .process_with_signers(
+                SplMinterIntstruction::Create(args),
+                accounts,
+                &[&mint, &state],
+            )
+            .await
+            .unwrap();
+        let escrow = get_associated_token_address(&vesting, &launch.mint);
+        assert_eq!(launch.token_balance(escrow).await, PREALLOCATION);
+    }
+}
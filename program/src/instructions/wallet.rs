@@ -0,0 +1,219 @@
+use super::create::{create_pda_account, State};
+use crate::{
+    error::TokenError,
+    instructions::{
+        schedule::RoundSchedule,
+        trade::{mul_div_u64, PERCENT_DENOMINATOR},
+    },
+};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+/// Everything one wallet bought through the curve and, after graduation, the
+/// pool, stored at `[b"wallet", state_account, wallet]`. Sells do not lower
+/// it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalletRecord {
+    pub state: Pubkey,
+    pub wallet: Pubkey,
+    pub bought_tokens: u64,
+    /// Stable paid, fees included.
+    pub spent_stable: u64,
+}
+
+impl WalletRecord {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+
+    pub fn address(program_id: &Pubkey, state_account: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"wallet", state_account.as_ref(), wallet.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Reads the record of `wallet`, creating it on the first buy.
+    pub fn load_or_create<'a>(
+        program_id: &Pubkey,
+        state_account: &AccountInfo<'a>,
+        wallet: &AccountInfo<'a>,
+        record_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+    ) -> Result<WalletRecord, ProgramError> {
+        let (address, bump) = WalletRecord::address(program_id, state_account.key, wallet.key);
+        if address != *record_account.key {
+            return Err(TokenError::WrongWalletAccount.into());
+        }
+        if record_account.owner == program_id {
+            return WalletRecord::try_from_slice(&record_account.data.borrow())
+                .map_err(|_| TokenError::WrongWalletAccount.into());
+        }
+        create_pda_account(
+            wallet,
+            record_account,
+            WalletRecord::LEN,
+            program_id,
+            system_program,
+            &[
+                b"wallet",
+                state_account.key.as_ref(),
+                wallet.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+        Ok(WalletRecord {
+            state: *state_account.key,
+            wallet: *wallet.key,
+            ..WalletRecord::default()
+        })
+    }
+}
+
+impl State {
+    /// Buys need the buyer's `WalletRecord` when any per-wallet cap is set.
+    pub fn has_wallet_caps(&self) -> bool {
+        self.wallet_token_cap > 0 || self.wallet_stable_cap > 0
+    }
+
+    /// Tokens and stable `record` may still buy, `u64::MAX` when uncapped.
+    pub fn wallet_room(&self, record: &WalletRecord) -> Result<(u64, u64), ProgramError> {
+        let room = |cap: u64, used: u64| {
+            if cap == 0 {
+                u64::MAX
+            } else {
+                cap.saturating_sub(used)
+            }
+        };
+        let tokens = room(self.wallet_token_cap, record.bought_tokens);
+        let stable = room(self.wallet_stable_cap, record.spent_stable);
+        if tokens == 0 || stable == 0 {
+            return Err(TokenError::WalletCapExceeded.into());
+        }
+        Ok((tokens, stable))
+    }
+
+    /// Errors when moving the curve from `supply` by `amount` tokens raises the
+    /// spot price by more than `max_price_impact`.
+    pub fn check_price_impact(
+        &self,
+        schedule: Option<&RoundSchedule>,
+        supply: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        if self.max_price_impact == 0 {
+            return Ok(());
+        }
+        self.check_price_rise(
+            self.curve.spot_price(schedule, supply)?,
+            self.curve.spot_price(schedule, supply + amount)?,
+        )
+    }
+
+    /// Errors when a buy moving the spot price from `before` to `after` raised
+    /// it by more than `max_price_impact`.
+    pub fn check_price_rise(&self, before: u64, after: u64) -> ProgramResult {
+        if self.max_price_impact == 0 {
+            return Ok(());
+        }
+        let before = before.max(1);
+        let impact = mul_div_u64(
+            after.saturating_sub(before),
+            100 * PERCENT_DENOMINATOR,
+            before,
+        )
+        .ok_or(TokenError::MathOverflow)?;
+        if impact > self.max_price_impact {
+            return Err(TokenError::PriceImpactTooHigh.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        instructions::{
+            create::{CreateTokenArgs, LaunchStatus},
+            trade::{mintTokensForExactStableArgs, ROUND_VOLUME},
+        },
+        processor::SplMinterIntstruction,
+        test_utils::*,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    fn buy(amount: u64) -> SplMinterIntstruction {
+        SplMinterIntstruction::MintTokensForExactStable(mintTokensForExactStableArgs {
+            _inAmount: amount,
+        })
+    }
+
+    async fn start(args: CreateTokenArgs) -> (TestLaunch, Vec<AccountMeta>) {
+        let launch = TestLaunch::start(args).await;
+        let (record, _) = WalletRecord::address(&launch.program_id, &launch.state, &launch.payer());
+        let mut accounts = launch.mint_accounts();
+        accounts.push(AccountMeta::new(record, false));
+        (launch, accounts)
+    }
+
+    #[tokio::test]
+    async fn token_cap_fills_wallet_up_to_it() {
+        let mut args = create_args(stepped_linear());
+        args.wallet_token_cap = ROUND_VOLUME;
+        let (mut launch, accounts) = start(args).await;
+
+        launch
+            .process(buy(500_000), accounts.clone())
+            .await
+            .unwrap();
+        launch
+            .process(buy(10_000_000), accounts.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(launch.payer_tokens()).await,
+            ROUND_VOLUME
+        );
+        assert_eq!(launch.state().await.status, LaunchStatus::Active);
+        let result = launch.process(buy(500_000), accounts).await;
+        assert_token_error(result, TokenError::WalletCapExceeded);
+    }
+
+    #[tokio::test]
+    async fn stable_cap_and_price_impact_limit_buys() {
+        let mut args = create_args(stepped_linear());
+        args.wallet_stable_cap = 800_000;
+        args.max_price_impact = 50 * PERCENT_DENOMINATOR;
+        let (mut launch, accounts) = start(args).await;
+
+        launch
+            .process(buy(500_000), accounts.clone())
+            .await
+            .unwrap();
+        launch
+            .process(buy(500_000), accounts.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            launch.token_balance(launch.payer_stable()).await,
+            PAYER_STABLE - 800_000
+        );
+        let result = launch.process(buy(500_000), accounts).await;
+        assert_token_error(result, TokenError::WalletCapExceeded);
+
+        // Crossing into the next round doubles the stepped price.
+        let mut args = create_args(stepped_linear());
+        args.max_price_impact = 50 * PERCENT_DENOMINATOR;
+        let mut launch = TestLaunch::start(args).await;
+        let result = launch.process(buy(2_000_000), launch.mint_accounts()).await;
+        assert_token_error(result, TokenError::PriceImpactTooHigh);
+        launch
+            .process(buy(500_000), launch.mint_accounts())
+            .await
+            .unwrap();
+    }
+}
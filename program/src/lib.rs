@@ -2,9 +2,12 @@ use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
 };
 
+pub mod curve;
 pub mod error;
 pub mod instructions;
 pub mod processor;
+#[cfg(test)]
+mod test_utils;
 
 entrypoint!(process_instruction);
 
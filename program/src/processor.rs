@@ -4,22 +4,66 @@ use {
 };
 
 use crate::instructions::{
+    allowlist::{mint_with_proof, MintWithProofArgs},
+    buyback::buyback,
+    candles::{init_candles, InitCandlesArgs},
     claim::{claim_token, ClaimArgs},
+    config::{claim_protocol_fees, create_launch, init_config, update_config, ConfigArgs},
     create::{create_token, CreateTokenArgs},
+    creator::claim_creator_fees,
+    freeze::{freeze_holder, revoke_freeze_authority, thaw_holder},
+    graduate::graduate,
+    metadata::{lock_metadata, update_metadata, UpdateMetadataArgs},
+    oracle::{get_twap, init_oracle, GetTwapArgs},
+    presale::refund,
+    referral::claim_referral,
+    registry::sync_registry_status,
+    schedule::{set_schedule, SetScheduleArgs},
+    staking::{claim_rewards, stake, unstake, StakeArgs},
+    supply::reconcile_supply,
     trade::{
         burnExactTokensForStable, burnExactTokensForStableArgs, mintTokensForExactStable,
         mintTokensForExactStableArgs, mintTokensForExactStableWithSlippage,
-        mintTokensForExactStableWithSlippageArgs,
+        mintTokensForExactStableWithSlippageArgs, quote, QuoteArgs,
     },
+    trading_window::{reschedule, RescheduleArgs},
+    vesting::claim_vested,
 };
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-enum SplMinterIntstruction {
+pub(crate) enum SplMinterIntstruction {
     Create(CreateTokenArgs),
     MintTokensForExactStableWithSlippage(mintTokensForExactStableWithSlippageArgs),
     MintTokensForExactStable(mintTokensForExactStableArgs),
     BurnExactTokensForStable(burnExactTokensForStableArgs),
     Claim(ClaimArgs),
+    ReconcileSupply,
+    Quote(QuoteArgs),
+    SetSchedule(SetScheduleArgs),
+    Graduate,
+    Refund,
+    Reschedule(RescheduleArgs),
+    MintTokensForExactStableWithProof(MintWithProofArgs),
+    InitOracle,
+    GetTwap(GetTwapArgs),
+    InitCandles(InitCandlesArgs),
+    ClaimReferral,
+    ClaimCreatorFees(ClaimArgs),
+    InitConfig(ConfigArgs),
+    UpdateConfig(ConfigArgs),
+    CreateLaunch(CreateTokenArgs),
+    ClaimProtocolFees(ClaimArgs),
+    SyncRegistryStatus,
+    UpdateMetadata(UpdateMetadataArgs),
+    LockMetadata,
+    RevokeFreezeAuthority,
+    FreezeHolder,
+    ThawHolder,
+    ClaimVested,
+    Buyback,
+    Stake(StakeArgs),
+    Unstake(StakeArgs),
+    ClaimRewards,
 }
 
 pub fn process_instruction(
@@ -41,6 +85,41 @@ pub fn process_instruction(
             burnExactTokensForStable(_program_id, accounts, args)
         }
         SplMinterIntstruction::Claim(args) => claim_token(_program_id, accounts, args),
+        SplMinterIntstruction::ReconcileSupply => reconcile_supply(_program_id, accounts),
+        SplMinterIntstruction::Quote(args) => quote(_program_id, accounts, args),
+        SplMinterIntstruction::SetSchedule(args) => set_schedule(_program_id, accounts, args),
+        SplMinterIntstruction::Graduate => graduate(_program_id, accounts),
+        SplMinterIntstruction::Refund => refund(_program_id, accounts),
+        SplMinterIntstruction::Reschedule(args) => reschedule(_program_id, accounts, args),
+        SplMinterIntstruction::MintTokensForExactStableWithProof(args) => {
+            mint_with_proof(_program_id, accounts, args)
+        }
+        SplMinterIntstruction::InitOracle => init_oracle(_program_id, accounts),
+        SplMinterIntstruction::GetTwap(args) => get_twap(_program_id, accounts, args),
+        SplMinterIntstruction::InitCandles(args) => init_candles(_program_id, accounts, args),
+        SplMinterIntstruction::ClaimReferral => claim_referral(_program_id, accounts),
+        SplMinterIntstruction::ClaimCreatorFees(args) => {
+            claim_creator_fees(_program_id, accounts, args)
+        }
+        SplMinterIntstruction::InitConfig(args) => init_config(_program_id, accounts, args),
+        SplMinterIntstruction::UpdateConfig(args) => update_config(_program_id, accounts, args),
+        SplMinterIntstruction::CreateLaunch(args) => create_launch(_program_id, accounts, args),
+        SplMinterIntstruction::ClaimProtocolFees(args) => {
+            claim_protocol_fees(_program_id, accounts, args)
+        }
+        SplMinterIntstruction::SyncRegistryStatus => sync_registry_status(_program_id, accounts),
+        SplMinterIntstruction::UpdateMetadata(args) => update_metadata(_program_id, accounts, args),
+        SplMinterIntstruction::LockMetadata => lock_metadata(_program_id, accounts),
+        SplMinterIntstruction::RevokeFreezeAuthority => {
+            revoke_freeze_authority(_program_id, accounts)
+        }
+        SplMinterIntstruction::FreezeHolder => freeze_holder(_program_id, accounts),
+        SplMinterIntstruction::ThawHolder => thaw_holder(_program_id, accounts),
+        SplMinterIntstruction::ClaimVested => claim_vested(_program_id, accounts),
+        SplMinterIntstruction::Buyback => buyback(_program_id, accounts),
+        SplMinterIntstruction::Stake(args) => stake(_program_id, accounts, args),
+        SplMinterIntstruction::Unstake(args) => unstake(_program_id, accounts, args),
+        SplMinterIntstruction::ClaimRewards => claim_rewards(_program_id, accounts),
     }
 }
 
@@ -68,7 +147,7 @@ mod test {
         let (mut banks_client, payer, recent_blockhash) = ProgramTest::new(
             "bpf_program_template",
             program_id,
-            processor!(process_instruction),
+            processor!(crate::test_utils::process_in_validator),
         )
         .start()
         .await;
@@ -0,0 +1,571 @@
+//! `solana-program-test` harness shared by the instruction tests.
+
+use crate::{
+    curve::{CurveConfig, CurveKind},
+    error::TokenError,
+    instructions::{
+        config::Config,
+        create::{CreateTokenArgs, State},
+        registry::{Registry, RegistryPage, REGISTRY_PAGE_SIZE},
+        vesting::Vesting,
+    },
+    processor::{process_instruction, SplMinterIntstruction},
+};
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey,
+        rent::Rent,
+        system_instruction, system_program, sysvar,
+    },
+    solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext},
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        bpf_loader,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::state::{Account as TokenAccount, Mint},
+    std::{cell::Cell, sync::Once},
+};
+
+/// Metadata program used by `create_token`, the same binary `start.sh` loads.
+const METAPLEX_SO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../metaplex.so");
+
+/// Stable minted to the payer at start, 6 decimals.
+pub const PAYER_STABLE: u64 = 1_000_000_000_000;
+
+pub fn create_args(curve: CurveConfig) -> CreateTokenArgs {
+    CreateTokenArgs {
+        token_title: "Test".to_string(),
+        token_symbol: "TST".to_string(),
+        token_uri: "https://example.com".to_string(),
+        claime_authority: Pubkey::default(),
+        curve,
+        graduation_threshold: 0,
+        pool_token_allocation: 0,
+        soft_cap: 0,
+        hard_cap: 0,
+        deadline: 0,
+        trading_starts_at: 0,
+        sells_enabled_at: 0,
+        allowlist_root: [0; 32],
+        allowlist_starts_at: 0,
+        wallet_token_cap: 0,
+        wallet_stable_cap: 0,
+        max_price_impact: 0,
+        launch_fee_rate: 0,
+        fee_decay_period: 0,
+        referral_share: 0,
+        creator: Pubkey::default(),
+        creator_fee_bps: 0,
+        pda_mint: false,
+        mint_nonce: 0,
+        no_freeze_authority: false,
+        vesting_amount: 0,
+        vesting_beneficiary: Pubkey::default(),
+        vesting_cliff: 0,
+        vesting_duration: 0,
+        buyback_bps: 0,
+        buyback_interval: 0,
+        buyback_cap: 0,
+        staking_fee_bps: 0,
+    }
+}
+
+pub fn stepped_linear() -> CurveConfig {
+    CurveConfig {
+        kind: CurveKind::SteppedLinear,
+        param_a: 0,
+        param_b: 0,
+    }
+}
+
+pub fn assert_token_error(result: Result<(), BanksClientError>, error: TokenError) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, error as u32),
+        other => panic!("expected {:?}, got {:?}", error, other),
+    }
+}
+
+thread_local! {
+    static IN_VALIDATOR: Cell<bool> = const { Cell::new(false) };
+}
+
+/// `process_instruction` as loaded into the test validator. It marks the
+/// thread as running inside the validator for `ValidatorStubs`.
+pub fn process_in_validator(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let outer = IN_VALIDATOR.with(|flag| flag.replace(true));
+    let result = process_instruction(program_id, accounts, instruction_data);
+    IN_VALIDATOR.with(|flag| flag.set(outer));
+    result
+}
+
+struct PlainStubs;
+
+impl SyscallStubs for PlainStubs {}
+
+/// The syscall stubs of `solana-program-test` are global and panic outside
+/// of a validator call, which broke plain unit tests that log. They are only
+/// used while `process_in_validator` runs; other threads get the defaults.
+struct ValidatorStubs {
+    validator: Box<dyn SyscallStubs>,
+}
+
+impl ValidatorStubs {
+    fn current(&self) -> &dyn SyscallStubs {
+        if IN_VALIDATOR.with(Cell::get) {
+            self.validator.as_ref()
+        } else {
+            &PlainStubs
+        }
+    }
+}
+
+impl SyscallStubs for ValidatorStubs {
+    fn sol_log(&self, message: &str) {
+        self.current().sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.current().sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.current().sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.current()
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.current().sol_get_last_restart_slot(var_addr)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.current().sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.current().sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.current().sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.current().sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.current().sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.current().sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        self.current().sol_log_data(fields)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.current().sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.current().sol_get_stack_height()
+    }
+}
+
+/// Wraps the stubs `solana-program-test` installs on its first start.
+fn isolate_validator_stubs() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let validator = set_syscall_stubs(Box::new(PlainStubs));
+        set_syscall_stubs(Box::new(ValidatorStubs { validator }));
+    });
+}
+
+/// A launch created through `Create`, with a funded payer.
+pub struct TestLaunch {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub mint: Pubkey,
+    pub state: Pubkey,
+    pub stable_mint: Pubkey,
+}
+
+impl TestLaunch {
+    /// `claime_authority` is replaced by the payer.
+    pub async fn start(args: CreateTokenArgs) -> Self {
+        let mut launch = TestLaunch::new().await;
+        launch.create(args).await;
+        launch
+    }
+
+    /// Starts the test validator with a funded payer. The launch itself is
+    /// made with `create`.
+    pub async fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "bpf_program_template",
+            program_id,
+            processor!(process_in_validator),
+        );
+        let metaplex = std::fs::read(METAPLEX_SO).expect("metaplex.so next to the program");
+        program_test.add_account(
+            mpl_token_metadata::ID,
+            Account {
+                lamports: Rent::default().minimum_balance(metaplex.len()),
+                data: metaplex,
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+        let context = program_test.start_with_context().await;
+        isolate_validator_stubs();
+
+        let stable_mint = Keypair::new();
+        let mut launch = TestLaunch {
+            context,
+            program_id,
+            mint: Pubkey::default(),
+            state: Pubkey::default(),
+            stable_mint: stable_mint.pubkey(),
+        };
+        launch.create_stable(&stable_mint).await;
+        let payer = launch.payer();
+        launch.set_upgrade_authority(Some(payer));
+        launch
+    }
+
+    /// `ProgramData` account of the program, as `solana program deploy` makes
+    /// it. `new` makes the payer its upgrade authority.
+    pub fn program_data(&self) -> Pubkey {
+        Pubkey::find_program_address(&[self.program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+    }
+
+    pub fn set_upgrade_authority(&mut self, authority: Option<Pubkey>) {
+        let state = UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: authority,
+        };
+        let account = AccountSharedData::new_data(
+            Rent::default().minimum_balance(UpgradeableLoaderState::size_of_programdata_metadata()),
+            &state,
+            &bpf_loader_upgradeable::id(),
+        )
+        .unwrap();
+        self.context.set_account(&self.program_data(), &account);
+    }
+
+    /// `claime_authority` is replaced by the payer.
+    pub async fn create(&mut self, args: CreateTokenArgs) {
+        self.try_create(args).await.unwrap();
+    }
+
+    /// `create` returning the result instead of unwrapping it.
+    pub async fn try_create(&mut self, mut args: CreateTokenArgs) -> Result<(), BanksClientError> {
+        let mint = Keypair::new();
+        let state = Keypair::new();
+        self.mint = mint.pubkey();
+        self.state = state.pubkey();
+
+        args.claime_authority = self.payer();
+        let mut accounts = self.create_accounts().await;
+        if args.vesting_amount > 0 {
+            let (vesting, _) = Vesting::address(&self.program_id, &self.state);
+            accounts.push(AccountMeta::new(vesting, false));
+            accounts.push(AccountMeta::new(
+                get_associated_token_address(&vesting, &self.mint),
+                false,
+            ));
+            accounts.push(AccountMeta::new(self.payer_stable(), false));
+        }
+        self.process_with_signers(
+            SplMinterIntstruction::Create(args),
+            accounts,
+            &[&mint, &state],
+        )
+        .await
+    }
+
+    /// Accounts of `Create` for `self.mint` and `self.state`, registered as
+    /// the next launch.
+    pub async fn create_accounts(&mut self) -> Vec<AccountMeta> {
+        let (registry, _) = Registry::address(&self.program_id);
+        let launch_count = match self
+            .context
+            .banks_client
+            .get_account(registry)
+            .await
+            .unwrap()
+        {
+            Some(account) if account.owner == self.program_id => {
+                Registry::try_from_slice(&account.data)
+                    .unwrap()
+                    .launch_count
+            }
+            _ => 0,
+        };
+        let (page, _) = RegistryPage::address(&self.program_id, launch_count / REGISTRY_PAGE_SIZE);
+        let (mint_authority, _) =
+            Pubkey::find_program_address(&[b"mint_authority"], &self.program_id);
+        let (metadata, _) = mpl_token_metadata::accounts::Metadata::find_pda(&self.mint);
+        vec![
+            AccountMeta::new(self.mint, true),
+            AccountMeta::new(mint_authority, false),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new(self.payer(), true),
+            AccountMeta::new(self.state, true),
+            AccountMeta::new(self.vault(), false),
+            AccountMeta::new_readonly(self.vault_authority(), false),
+            AccountMeta::new_readonly(self.stable_mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new(registry, false),
+            AccountMeta::new(page, false),
+            AccountMeta::new_readonly(Config::address(&self.program_id).0, false),
+        ]
+    }
+
+    async fn create_stable(&mut self, stable_mint: &Keypair) {
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &stable_mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &stable_mint.pubkey(),
+                &payer,
+                None,
+                6,
+            )
+            .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                &payer,
+                &stable_mint.pubkey(),
+                &spl_token::id(),
+            ),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &stable_mint.pubkey(),
+                &self.payer_stable(),
+                &payer,
+                &[],
+                PAYER_STABLE,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[stable_mint]).await.unwrap();
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub fn payer_stable(&self) -> Pubkey {
+        get_associated_token_address(&self.payer(), &self.stable_mint)
+    }
+
+    pub fn payer_tokens(&self) -> Pubkey {
+        get_associated_token_address(&self.payer(), &self.mint)
+    }
+
+    pub fn vault_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"vault", self.state.as_ref()], &self.program_id).0
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        get_associated_token_address(&self.vault_authority(), &self.stable_mint)
+    }
+
+    /// Fixed accounts of `MintTokensForExactStable`.
+    pub fn mint_accounts(&self) -> Vec<AccountMeta> {
+        let (mint_authority, _) =
+            Pubkey::find_program_address(&[b"mint_authority"], &self.program_id);
+        vec![
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(mint_authority, false),
+            AccountMeta::new(self.payer_tokens(), false),
+            AccountMeta::new(self.payer(), true),
+            AccountMeta::new(self.state, false),
+            AccountMeta::new(self.payer_stable(), false),
+            AccountMeta::new(self.vault(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ]
+    }
+
+    /// Fixed accounts of `BurnExactTokensForStable`.
+    pub fn burn_accounts(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new(self.payer_tokens(), false),
+            AccountMeta::new(self.payer(), true),
+            AccountMeta::new(self.state, false),
+            AccountMeta::new(self.payer_stable(), false),
+            AccountMeta::new_readonly(self.vault_authority(), false),
+            AccountMeta::new(self.vault(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]
+    }
+
+    pub async fn process(
+        &mut self,
+        instruction: SplMinterIntstruction,
+        accounts: Vec<AccountMeta>,
+    ) -> Result<(), BanksClientError> {
+        self.process_with_signers(instruction, accounts, &[]).await
+    }
+
+    pub async fn process_with_signers(
+        &mut self,
+        instruction: SplMinterIntstruction,
+        accounts: Vec<AccountMeta>,
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction::new_with_bytes(
+            self.program_id,
+            &instruction.try_to_vec().unwrap(),
+            accounts,
+        );
+        self.send(&[instruction], signers).await
+    }
+
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer()),
+            &all_signers,
+            blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    /// Simulates `instruction` and returns the data it set with
+    /// `set_return_data`.
+    pub async fn view(
+        &mut self,
+        instruction: SplMinterIntstruction,
+        accounts: Vec<AccountMeta>,
+    ) -> Vec<u8> {
+        let instruction = Instruction::new_with_bytes(
+            self.program_id,
+            &instruction.try_to_vec().unwrap(),
+            accounts,
+        );
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer()),
+            &[&self.context.payer],
+            blockhash,
+        );
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| return_data.data)
+            .unwrap_or_default()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    pub async fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+
+    pub async fn state(&mut self) -> State {
+        let account = self.account(self.state).await;
+        State::try_from_slice(&account.data).unwrap()
+    }
+
+    pub async fn token_balance(&mut self, address: Pubkey) -> u64 {
+        TokenAccount::unpack(&self.account(address).await.data)
+            .unwrap()
+            .amount
+    }
+
+    pub async fn mint_supply(&mut self) -> u64 {
+        Mint::unpack(&self.account(self.mint).await.data)
+            .unwrap()
+            .supply
+    }
+
+    pub async fn account(&mut self, address: Pubkey) -> Account {
+        self.context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+}
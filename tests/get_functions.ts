@@ -1,7 +1,7 @@
 import { Connection, PublicKey } from '@solana/web3.js';
 import { getMint } from '@solana/spl-token';
-import { struct } from '@solana/buffer-layout';
-import { publicKey, u64 } from '@solana/buffer-layout-utils';
+import { blob, struct, u8 } from '@solana/buffer-layout';
+import { publicKey, u64, u128 } from '@solana/buffer-layout-utils';
 import { InitArgs } from './init_test';
 
 export interface accountStateData {
@@ -10,6 +10,65 @@ export interface accountStateData {
 	total: bigint;
 	claimed: bigint;
 	claime_authority: PublicKey;
+	curve_supply: bigint;
+	curve_kind: number;
+	curve_param_a: bigint;
+	curve_param_b: bigint;
+	status: number;
+	graduation_threshold: bigint;
+	pool_token_allocation: bigint;
+	reserve: bigint;
+	pool_stable: bigint;
+	pool_tokens: bigint;
+	soft_cap: bigint;
+	hard_cap: bigint;
+	deadline: bigint;
+	raised: bigint;
+	trading_starts_at: bigint;
+	sells_enabled_at: bigint;
+	allowlist_root: Uint8Array;
+	allowlist_starts_at: bigint;
+	wallet_token_cap: bigint;
+	wallet_stable_cap: bigint;
+	max_price_impact: bigint;
+	launch_fee_rate: bigint;
+	fee_decay_period: bigint;
+	buy_volume: bigint;
+	sell_volume: bigint;
+	buy_count: bigint;
+	sell_count: bigint;
+	ath_price: bigint;
+	ath_round: bigint;
+	last_trade_slot: bigint;
+	unique_buyers: bigint;
+	price_cumulative: bigint;
+	last_price: bigint;
+	price_updated_at: bigint;
+	oracle_enabled: number;
+	candles_enabled: number;
+	referral_share: bigint;
+	creator: PublicKey;
+	creator_fee_bps: bigint;
+	creator_fees: bigint;
+	creator_claimed: bigint;
+	config: PublicKey;
+	launch_id: bigint;
+	protocol_fee_bps: bigint;
+	protocol_claimed: bigint;
+	registry_index: bigint;
+	denylist_enabled: number;
+	vesting_amount: bigint;
+	buyback_bps: bigint;
+	buyback_interval: bigint;
+	buyback_cap: bigint;
+	buyback_spent: bigint;
+	buyback_burned: bigint;
+	last_buyback_at: bigint;
+	staking_fee_bps: bigint;
+	total_staked: bigint;
+	reward_per_token: bigint;
+	staking_rewards: bigint;
+	staking_claimed: bigint;
 }
 export const StateLayout = struct<accountStateData>([
 	publicKey('mint_account'),
@@ -17,6 +76,65 @@ export const StateLayout = struct<accountStateData>([
 	u64('total'),
 	u64('claimed'),
 	publicKey('claime_authority'),
+	u64('curve_supply'),
+	u8('curve_kind'),
+	u64('curve_param_a'),
+	u64('curve_param_b'),
+	u8('status'),
+	u64('graduation_threshold'),
+	u64('pool_token_allocation'),
+	u64('reserve'),
+	u64('pool_stable'),
+	u64('pool_tokens'),
+	u64('soft_cap'),
+	u64('hard_cap'),
+	u64('deadline'),
+	u64('raised'),
+	u64('trading_starts_at'),
+	u64('sells_enabled_at'),
+	blob(32, 'allowlist_root'),
+	u64('allowlist_starts_at'),
+	u64('wallet_token_cap'),
+	u64('wallet_stable_cap'),
+	u64('max_price_impact'),
+	u64('launch_fee_rate'),
+	u64('fee_decay_period'),
+	u64('buy_volume'),
+	u64('sell_volume'),
+	u64('buy_count'),
+	u64('sell_count'),
+	u64('ath_price'),
+	u64('ath_round'),
+	u64('last_trade_slot'),
+	u64('unique_buyers'),
+	u128('price_cumulative'),
+	u64('last_price'),
+	u64('price_updated_at'),
+	u8('oracle_enabled'),
+	u8('candles_enabled'),
+	u64('referral_share'),
+	publicKey('creator'),
+	u64('creator_fee_bps'),
+	u64('creator_fees'),
+	u64('creator_claimed'),
+	publicKey('config'),
+	u64('launch_id'),
+	u64('protocol_fee_bps'),
+	u64('protocol_claimed'),
+	u64('registry_index'),
+	u8('denylist_enabled'),
+	u64('vesting_amount'),
+	u64('buyback_bps'),
+	u64('buyback_interval'),
+	u64('buyback_cap'),
+	u64('buyback_spent'),
+	u64('buyback_burned'),
+	u64('last_buyback_at'),
+	u64('staking_fee_bps'),
+	u64('total_staked'),
+	u128('reward_per_token'),
+	u64('staking_rewards'),
+	u64('staking_claimed'),
 ]);
 
 const START_PRICE = BigInt(1);
@@ -32,6 +150,8 @@ const PERCENT_DENOMINATOR = BigInt(10000);
 export class Args {
 	connection: Connection;
 	mintAccount: PublicKey;
+	// when set, pricing follows the curve supply kept in State
+	stateAccount?: PublicKey;
 }
 
 export async function calcMintTokensForExactStable_calcMintTokensForExactStable(
@@ -119,6 +239,13 @@ export async function _priceInUnitsAndRemainByRound(
 }
 
 export async function _distributedAmount(args: Args): Promise<bigint> {
+	if (args.stateAccount) {
+		let stateAccount = await args.connection.getAccountInfo(
+			args.stateAccount,
+			'processed',
+		);
+		return StateLayout.decode(stateAccount.data).curve_supply;
+	}
 	let mintAccount = await getMint(args.connection, args.mintAccount);
 
 	return BigInt(mintAccount.supply);
@@ -153,6 +280,134 @@ export async function getState(
 	return StateLayout.decode(stateAccount.data);
 }
 
+export interface userStatsData {
+	state: PublicKey;
+	wallet: PublicKey;
+	stable_in: bigint;
+	stable_out: bigint;
+	tokens_bought: bigint;
+	tokens_sold: bigint;
+	trade_count: bigint;
+	cost_basis: bigint;
+	referrer: PublicKey;
+}
+export const UserStatsLayout = struct<userStatsData>([
+	publicKey('state'),
+	publicKey('wallet'),
+	u64('stable_in'),
+	u64('stable_out'),
+	u64('tokens_bought'),
+	u64('tokens_sold'),
+	u64('trade_count'),
+	u64('cost_basis'),
+	publicKey('referrer'),
+]);
+
+export function userStatsAddress(
+	programId: PublicKey,
+	stateAddress: PublicKey,
+	wallet: PublicKey,
+): PublicKey {
+	return PublicKey.findProgramAddressSync(
+		[Buffer.from('stats'), stateAddress.toBuffer(), wallet.toBuffer()],
+		programId,
+	)[0];
+}
+
+// null until the wallet makes its first trade passing the stats account.
+export async function getUserStats(
+	connection: Connection,
+	programId: PublicKey,
+	stateAddress: PublicKey,
+	wallet: PublicKey,
+): Promise<userStatsData | null> {
+	let statsAccount = await connection.getAccountInfo(
+		userStatsAddress(programId, stateAddress, wallet),
+		'processed',
+	);
+	return statsAccount ? UserStatsLayout.decode(statsAccount.data) : null;
+}
+
+// Stable paid per whole token still held.
+export function averageCost(stats: userStatsData): bigint {
+	const position = stats.tokens_bought - stats.tokens_sold;
+	if (position <= BigInt(0)) {
+		return BigInt(0);
+	}
+	return (stats.cost_basis * BigInt(10 ** _distributionTokenDecimals)) / position;
+}
+
+export const REGISTRY_PAGE_SIZE = 32;
+
+export interface launchRecordData {
+	state: PublicKey;
+	mint: PublicKey;
+	creator: PublicKey;
+	trade_token: PublicKey;
+	created_slot: bigint;
+	status: number;
+}
+export const LaunchRecordLayout = struct<launchRecordData>([
+	publicKey('state'),
+	publicKey('mint'),
+	publicKey('creator'),
+	publicKey('trade_token'),
+	u64('created_slot'),
+	u8('status'),
+]);
+
+export function registryAddress(programId: PublicKey): PublicKey {
+	return PublicKey.findProgramAddressSync([Buffer.from('registry')], programId)[0];
+}
+
+export function configAddress(programId: PublicKey): PublicKey {
+	return PublicKey.findProgramAddressSync([Buffer.from('config')], programId)[0];
+}
+
+export function registryPageAddress(programId: PublicKey, page: bigint): PublicKey {
+	const seed = Buffer.alloc(8);
+	seed.writeBigUInt64LE(page);
+	return PublicKey.findProgramAddressSync([Buffer.from('registry'), seed], programId)[0];
+}
+
+export async function getLaunchCount(
+	connection: Connection,
+	programId: PublicKey,
+): Promise<bigint> {
+	let registryAccount = await connection.getAccountInfo(
+		registryAddress(programId),
+		'processed',
+	);
+	return registryAccount ? registryAccount.data.readBigUInt64LE(0) : BigInt(0);
+}
+
+// Launches of one page in creation order, empty past the last page.
+export async function getRegistryPage(
+	connection: Connection,
+	programId: PublicKey,
+	page: bigint,
+): Promise<launchRecordData[]> {
+	let pageAccount = await connection.getAccountInfo(
+		registryPageAddress(programId, page),
+		'processed',
+	);
+	if (!pageAccount) {
+		return [];
+	}
+	const records: launchRecordData[] = [];
+	// u64 page number and u32 vector length come first.
+	for (let i = 0; i < REGISTRY_PAGE_SIZE; i++) {
+		const record = LaunchRecordLayout.decode(
+			pageAccount.data,
+			12 + i * LaunchRecordLayout.span,
+		);
+		if (!record.state.equals(PublicKey.default)) {
+			records.push(record);
+		}
+	}
+	return records;
+}
+
 export async function calcMintStableForExactTokens(
 	_outAmount: bigint,
 	args: Args,
@@ -48,15 +48,19 @@ export function getStateAddress(key: Keypair): PublicKey {
 export async function getpda_trade_token_ata_account(
 	mint_acc: Keypair,
 	program_id: Keypair,
+	state: PublicKey,
 ): Promise<PublicKey> {
-	const pda = getPDATradeTokenAuthorityAddress(program_id);
+	const pda = getPDATradeTokenAuthorityAddress(program_id, state);
 
 	return await getAssociatedTokenAddress(mint_acc.publicKey, pda, true);
 }
 
-export function getPDATradeTokenAuthorityAddress(key: Keypair): PublicKey {
+export function getPDATradeTokenAuthorityAddress(
+	key: Keypair,
+	state: PublicKey,
+): PublicKey {
 	return PublicKey.findProgramAddressSync(
-		[Buffer.from('ata_trade_token_authority')],
+		[Buffer.from('vault'), state.toBuffer()],
 		key.publicKey,
 	)[0];
 }
@@ -92,9 +96,12 @@ export async function init_keypairs_trade_token(
 	const pda_trade_token_ata_account = await getpda_trade_token_ata_account(
 		tradeTokenKeypair,
 		program,
+		stateAddress,
+	);
+	const PDATradeTokenAuthorityAddress = getPDATradeTokenAuthorityAddress(
+		program,
+		stateAddress,
 	);
-	const PDATradeTokenAuthorityAddress =
-		getPDATradeTokenAuthorityAddress(program);
 
 	return {
 		payer,
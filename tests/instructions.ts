@@ -14,10 +14,91 @@ export enum SplMinterInstruction {
     MintTokensForExactStableWithSlippage,
     MintTokensForExactStable,
     BurnExactTokensForStable,
-    Claim
+    Claim,
+    ReconcileSupply,
+    Quote,
+    SetSchedule,
+    Graduate,
+    Refund,
+    Reschedule,
+    MintTokensForExactStableWithProof,
+    InitOracle,
+    GetTwap,
+    InitCandles,
+    ClaimReferral,
+    ClaimCreatorFees,
+    InitConfig,
+    UpdateConfig,
+    CreateLaunch,
+    ClaimProtocolFees,
+    SyncRegistryStatus,
+    UpdateMetadata,
+    LockMetadata,
+    RevokeFreezeAuthority,
+    FreezeHolder,
+    ThawHolder,
+    ClaimVested,
+    Buyback,
+    Stake,
+    Unstake,
+    ClaimRewards
 }
 
+export enum CurveKind {
+    SteppedLinear,
+    Exponential,
+    ContinuousLinear,
+    ConstantProduct,
+    Schedule
+}
+
+export enum LaunchStatus {
+    Active,
+    SaleCompleted,
+    Graduated,
+    Succeeded,
+    Failed
+}
+
+// Fields added after the first release, so older callers keep working.
+const CREATE_TOKEN_DEFAULTS = {
+    curve_kind: CurveKind.SteppedLinear,
+    curve_param_a: BigInt(0),
+    curve_param_b: BigInt(0),
+    graduation_threshold: BigInt(0),
+    pool_token_allocation: BigInt(0),
+    soft_cap: BigInt(0),
+    hard_cap: BigInt(0),
+    deadline: BigInt(0),
+    trading_starts_at: BigInt(0),
+    sells_enabled_at: BigInt(0),
+    allowlist_root: new Uint8Array(32),
+    allowlist_starts_at: BigInt(0),
+    wallet_token_cap: BigInt(0),
+    wallet_stable_cap: BigInt(0),
+    max_price_impact: BigInt(0),
+    launch_fee_rate: BigInt(0),
+    fee_decay_period: BigInt(0),
+    referral_share: BigInt(0),
+    creator: new Uint8Array(32),
+    creator_fee_bps: BigInt(0),
+    pda_mint: 0,
+    mint_nonce: BigInt(0),
+    no_freeze_authority: 0,
+    vesting_amount: BigInt(0),
+    vesting_beneficiary: new Uint8Array(32),
+    vesting_cliff: BigInt(0),
+    vesting_duration: BigInt(0),
+    buyback_bps: BigInt(0),
+    buyback_interval: BigInt(0),
+    buyback_cap: BigInt(0),
+    staking_fee_bps: BigInt(0),
+};
+
 export class CreateTokenArgs extends Assignable {
+    constructor(properties) {
+        super({ ...CREATE_TOKEN_DEFAULTS, ...properties });
+    };
     toBuffer() {
         return Buffer.from(borsh.serialize(CreateTokenArgsSchema, this));
     }
@@ -33,6 +114,37 @@ const CreateTokenArgsSchema = new Map([
                 ['token_symbol', 'string'],
                 ['token_uri', 'string'],
                 ['claim_authority', [32]],
+                ['curve_kind', 'u8'],
+                ['curve_param_a', 'u64'],
+                ['curve_param_b', 'u64'],
+                ['graduation_threshold', 'u64'],
+                ['pool_token_allocation', 'u64'],
+                ['soft_cap', 'u64'],
+                ['hard_cap', 'u64'],
+                ['deadline', 'u64'],
+                ['trading_starts_at', 'u64'],
+                ['sells_enabled_at', 'u64'],
+                ['allowlist_root', [32]],
+                ['allowlist_starts_at', 'u64'],
+                ['wallet_token_cap', 'u64'],
+                ['wallet_stable_cap', 'u64'],
+                ['max_price_impact', 'u64'],
+                ['launch_fee_rate', 'u64'],
+                ['fee_decay_period', 'u64'],
+                ['referral_share', 'u64'],
+                ['creator', [32]],
+                ['creator_fee_bps', 'u64'],
+                ['pda_mint', 'u8'],
+                ['mint_nonce', 'u64'],
+                ['no_freeze_authority', 'u8'],
+                ['vesting_amount', 'u64'],
+                ['vesting_beneficiary', [32]],
+                ['vesting_cliff', 'u64'],
+                ['vesting_duration', 'u64'],
+                ['buyback_bps', 'u64'],
+                ['buyback_interval', 'u64'],
+                ['buyback_cap', 'u64'],
+                ['staking_fee_bps', 'u64'],
             ]
         }
     ]
@@ -126,6 +238,181 @@ const BurnExactTokensForStableToArgsSchema = new Map([
     ]
 ]);
 
+export class QuoteArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(QuoteArgsSchema, this));
+    }
+};
+const QuoteArgsSchema = new Map([
+    [
+        QuoteArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['is_buy', 'u8'],
+                ['_inAmount', 'u64'],
+            ]
+        }
+    ]
+]);
+
+export class ScheduleRound extends Assignable {}
+
+export class SetScheduleArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(SetScheduleArgsSchema, this));
+    }
+};
+const SetScheduleArgsSchema = new Map<any, any>([
+    [
+        SetScheduleArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['rounds', [ScheduleRound]],
+            ]
+        }
+    ],
+    [
+        ScheduleRound, {
+            kind: 'struct',
+            fields: [
+                ['end_supply', 'u64'],
+                ['price', 'u64'],
+            ]
+        }
+    ]
+]);
+
+export class RescheduleArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(RescheduleArgsSchema, this));
+    }
+};
+const RescheduleArgsSchema = new Map([
+    [
+        RescheduleArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['trading_starts_at', 'u64'],
+                ['sells_enabled_at', 'u64'],
+            ]
+        }
+    ]
+]);
+
+export class MintWithProofArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(MintWithProofArgsSchema, this));
+    }
+};
+const MintWithProofArgsSchema = new Map([
+    [
+        MintWithProofArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['in_amount', 'u64'],
+                ['out_not_less', 'u64'],
+                ['allocation', 'u64'],
+                ['proof', [[32]]],
+            ]
+        }
+    ]
+]);
+
+export class GetTwapArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(GetTwapArgsSchema, this));
+    }
+};
+const GetTwapArgsSchema = new Map([
+    [
+        GetTwapArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['window', 'u64'],
+            ]
+        }
+    ]
+]);
+
+export class InitCandlesArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(InitCandlesArgsSchema, this));
+    }
+};
+const InitCandlesArgsSchema = new Map([
+    [
+        InitCandlesArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['interval', 'u64'],
+            ]
+        }
+    ]
+]);
+
+export class ConfigArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(ConfigArgsSchema, this));
+    }
+};
+const ConfigArgsSchema = new Map([
+    [
+        ConfigArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['admin', [32]],
+                ['treasury', [32]],
+                ['protocol_fee_bps', 'u64'],
+                ['creation_fee', 'u64'],
+                ['trade_tokens', [[32]]],
+            ]
+        }
+    ]
+]);
+
+export class UpdateMetadataArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(UpdateMetadataArgsSchema, this));
+    }
+};
+const UpdateMetadataArgsSchema = new Map([
+    [
+        UpdateMetadataArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['token_title', 'string'],
+                ['token_symbol', 'string'],
+                ['token_uri', 'string'],
+            ]
+        }
+    ]
+]);
+
+export class StakeArgs extends Assignable {
+    toBuffer() {
+        return Buffer.from(borsh.serialize(StakeArgsSchema, this));
+    }
+};
+const StakeArgsSchema = new Map([
+    [
+        StakeArgs, {
+            kind: 'struct',
+            fields: [
+                ['instruction', 'u8'],
+                ['amount', 'u64'],
+            ]
+        }
+    ]
+]);
+
 export class StateAccoundData extends Assignable {}
 
 export const dataStateSchema = new Map([
@@ -41,6 +41,11 @@ import {
 	_calcMintStableForExactTokens,
 	_calcBurnExactTokensForStable,
 	_calcBurnTokensForExactStable,
+	getLaunchCount,
+	REGISTRY_PAGE_SIZE,
+	registryAddress,
+	registryPageAddress,
+	configAddress,
 } from './get_functions';
 import { assert } from 'chai';
 
@@ -187,6 +192,25 @@ describe('SPL Token Minter', async () => {
 					isSigner: false,
 					isWritable: false,
 				}, // Associated Token  program
+				{
+					pubkey: registryAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry
+				{
+					pubkey: registryPageAddress(
+						args.program.publicKey,
+						(await getLaunchCount(connection, args.program.publicKey)) /
+							BigInt(REGISTRY_PAGE_SIZE),
+					),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry page
+				{
+					pubkey: configAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: false,
+				}, // Config
 			],
 			programId: args.program.publicKey,
 			data: instructionData.toBuffer(),
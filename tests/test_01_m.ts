@@ -34,6 +34,11 @@ import {
 	_calcBurnExactTokensForStable,
 	_calcBurnTokensForExactStable,
 	_priceInUnitsAndRemainByRound,
+	getLaunchCount,
+	REGISTRY_PAGE_SIZE,
+	registryAddress,
+	registryPageAddress,
+	configAddress,
 } from './get_functions';
 
 import { assert } from 'chai';
@@ -153,6 +158,25 @@ describe('ChebuTokenTest_m_01', async () => {
 					isSigner: false,
 					isWritable: false,
 				}, // Associated Token  program
+				{
+					pubkey: registryAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry
+				{
+					pubkey: registryPageAddress(
+						args.program.publicKey,
+						(await getLaunchCount(connection, args.program.publicKey)) /
+							BigInt(REGISTRY_PAGE_SIZE),
+					),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry page
+				{
+					pubkey: configAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: false,
+				}, // Config
 			],
 			programId: args.program.publicKey,
 			data: instructionData.toBuffer(),
@@ -34,6 +34,11 @@ import {
 	_calcMintStableForExactTokens,
 	_calcBurnExactTokensForStable,
 	_calcBurnTokensForExactStable,
+	getLaunchCount,
+	REGISTRY_PAGE_SIZE,
+	registryAddress,
+	registryPageAddress,
+	configAddress,
 } from './get_functions';
 import { assert } from 'chai';
 
@@ -147,6 +152,25 @@ describe('ChebuTokenTest_a_02', async () => {
 					isSigner: false,
 					isWritable: false,
 				}, // Associated Token  program
+				{
+					pubkey: registryAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry
+				{
+					pubkey: registryPageAddress(
+						args.program.publicKey,
+						(await getLaunchCount(connection, args.program.publicKey)) /
+							BigInt(REGISTRY_PAGE_SIZE),
+					),
+					isSigner: false,
+					isWritable: true,
+				}, // Registry page
+				{
+					pubkey: configAddress(args.program.publicKey),
+					isSigner: false,
+					isWritable: false,
+				}, // Config
 			],
 			programId: args.program.publicKey,
 			data: instructionData.toBuffer(),
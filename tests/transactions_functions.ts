@@ -61,14 +61,18 @@ export function getStateAddress(key: Keypair): PublicKey {
 export async function getpda_trade_token_ata_account(
 	mint_acc: Keypair,
 	program_id: PublicKey,
+	state: PublicKey,
 ): Promise<PublicKey> {
-	const pda = getPDATradeTokenAuthorityAddress(program_id);
+	const pda = getPDATradeTokenAuthorityAddress(program_id, state);
 	return await getAssociatedTokenAddress(mint_acc.publicKey, pda, true);
 }
 
-export function getPDATradeTokenAuthorityAddress(key: PublicKey): PublicKey {
+export function getPDATradeTokenAuthorityAddress(
+	key: PublicKey,
+	state: PublicKey,
+): PublicKey {
 	return PublicKey.findProgramAddressSync(
-		[Buffer.from('ata_trade_token_authority')],
+		[Buffer.from('vault'), state.toBuffer()],
 		key,
 	)[0];
 }
@@ -342,6 +346,7 @@ export async function burnExactTokensForStable(
 
 	const pda_trade_token_authority = getPDATradeTokenAuthorityAddress(
 		args.program.publicKey,
+		args.stateAddress,
 	);
 
 	let ix_1 = new TransactionInstruction({